@@ -4,6 +4,24 @@ mod m20240225_224934_create_user;
 mod m20240227_020126_create_post;
 mod m20240309_230819_create_user_token;
 mod m20240309_230808_create_snapshot;
+mod m20240415_184512_create_tag;
+mod m20240415_184520_create_tag_name;
+mod m20240415_184527_create_post_tag;
+mod m20240421_153040_create_tag_category;
+mod m20240423_184530_create_tag_implication;
+mod m20240423_184535_create_tag_suggestion;
+mod m20240428_153210_create_post_score;
+mod m20240428_153220_create_post_favorite;
+mod m20240430_201500_create_comment;
+mod m20240430_201510_create_comment_score;
+mod m20240505_141000_create_pool_category;
+mod m20240505_141010_create_pool;
+mod m20240505_141020_create_pool_name;
+mod m20240505_141030_create_pool_post;
+mod m20240507_183000_create_post_note;
+mod m20240508_102000_create_post_relation;
+mod m20240512_120000_add_post_custom_thumbnail;
+mod m20240512_130000_add_tag_name_lower_index;
 
 pub struct Migrator;
 
@@ -15,6 +33,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20240227_020126_create_post::Migration),
             Box::new(m20240309_230808_create_snapshot::Migration),
             Box::new(m20240309_230819_create_user_token::Migration),
+            Box::new(m20240415_184512_create_tag::Migration),
+            Box::new(m20240415_184520_create_tag_name::Migration),
+            Box::new(m20240415_184527_create_post_tag::Migration),
+            Box::new(m20240421_153040_create_tag_category::Migration),
+            Box::new(m20240423_184530_create_tag_implication::Migration),
+            Box::new(m20240423_184535_create_tag_suggestion::Migration),
+            Box::new(m20240428_153210_create_post_score::Migration),
+            Box::new(m20240428_153220_create_post_favorite::Migration),
+            Box::new(m20240430_201500_create_comment::Migration),
+            Box::new(m20240430_201510_create_comment_score::Migration),
+            Box::new(m20240505_141000_create_pool_category::Migration),
+            Box::new(m20240505_141010_create_pool::Migration),
+            Box::new(m20240505_141020_create_pool_name::Migration),
+            Box::new(m20240505_141030_create_pool_post::Migration),
+            Box::new(m20240507_183000_create_post_note::Migration),
+            Box::new(m20240508_102000_create_post_relation::Migration),
+            Box::new(m20240512_120000_add_post_custom_thumbnail::Migration),
+            Box::new(m20240512_130000_add_tag_name_lower_index::Migration),
         ]
     }
 }
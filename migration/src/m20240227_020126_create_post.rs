@@ -54,7 +54,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum Post {
+pub(super) enum Post {
     Table,
     Id,
     #[sea_orm(iden = "user_id")]
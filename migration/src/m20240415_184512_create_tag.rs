@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tag::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tag::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tag::Category).string_len(32).not_null())
+                    .col(ColumnDef::new(Tag::Description).text())
+                    .col(ColumnDef::new(Tag::CreationTime).timestamp().not_null())
+                    .col(ColumnDef::new(Tag::LastEditTime).timestamp())
+                    .col(ColumnDef::new(Tag::Version).integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Tag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub(super) enum Tag {
+    Table,
+    Id,
+    Category,
+    Description,
+    #[sea_orm(iden = "creation_time")]
+    CreationTime,
+    #[sea_orm(iden = "last_edit_time")]
+    LastEditTime,
+    Version,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240415_184512_create_tag::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TagName::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TagName::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TagName::TagId).integer().not_null())
+                    .col(
+                        ColumnDef::new(TagName::Name)
+                            .string_len(128)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(TagName::Ord).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_tag_name_tagid")
+                            .from(TagName::Table, TagName::TagId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TagName::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TagName {
+    Table,
+    Id,
+    #[sea_orm(iden = "tag_id")]
+    TagId,
+    Name,
+    Ord,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240227_020126_create_post::Post;
+use crate::m20240415_184512_create_tag::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostTag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PostTag::PostId).integer().not_null())
+                    .col(ColumnDef::new(PostTag::TagId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PostTag::PostId)
+                            .col(PostTag::TagId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_tag_postid")
+                            .from(PostTag::Table, PostTag::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_tag_tagid")
+                            .from(PostTag::Table, PostTag::TagId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostTag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostTag {
+    Table,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    #[sea_orm(iden = "tag_id")]
+    TagId,
+}
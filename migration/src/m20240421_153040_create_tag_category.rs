@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240415_184512_create_tag::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TagCategory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TagCategory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TagCategory::Name)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(TagCategory::Color).string_len(32).not_null())
+                    .col(ColumnDef::new(TagCategory::Order).integer().not_null())
+                    .col(ColumnDef::new(TagCategory::IsDefault).boolean().not_null())
+                    .col(ColumnDef::new(TagCategory::Version).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        // Every tag needs a category, existing tags keep theirs
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(TagCategory::Table)
+                    .columns([TagCategory::Name, TagCategory::Color, TagCategory::Order, TagCategory::IsDefault, TagCategory::Version])
+                    .values_panic(["default".into(), "default".into(), 1.into(), true.into(), 1.into()])
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "tag_category" ("name", "color", "order", "default", "version")
+                SELECT DISTINCT "category", 'default', 1, false, 1 FROM "tag" WHERE "category" <> 'default'"#,
+            )
+            .await?;
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("FK_tag_category")
+                    .from(Tag::Table, Tag::Category)
+                    .to(TagCategory::Table, TagCategory::Name)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .on_delete(ForeignKeyAction::Restrict)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("FK_tag_category")
+                    .table(Tag::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TagCategory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TagCategory {
+    Table,
+    Id,
+    Name,
+    Color,
+    Order,
+    #[sea_orm(iden = "default")]
+    IsDefault,
+    Version,
+}
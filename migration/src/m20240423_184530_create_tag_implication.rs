@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240415_184512_create_tag::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TagImplication::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TagImplication::ParentId).integer().not_null())
+                    .col(ColumnDef::new(TagImplication::ChildId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(TagImplication::ParentId)
+                            .col(TagImplication::ChildId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_tag_implication_parentid")
+                            .from(TagImplication::Table, TagImplication::ParentId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_tag_implication_childid")
+                            .from(TagImplication::Table, TagImplication::ChildId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TagImplication::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TagImplication {
+    Table,
+    #[sea_orm(iden = "parent_id")]
+    ParentId,
+    #[sea_orm(iden = "child_id")]
+    ChildId,
+}
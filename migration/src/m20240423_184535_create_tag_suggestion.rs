@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240415_184512_create_tag::Tag;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TagSuggestion::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TagSuggestion::ParentId).integer().not_null())
+                    .col(ColumnDef::new(TagSuggestion::ChildId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(TagSuggestion::ParentId)
+                            .col(TagSuggestion::ChildId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_tag_suggestion_parentid")
+                            .from(TagSuggestion::Table, TagSuggestion::ParentId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_tag_suggestion_childid")
+                            .from(TagSuggestion::Table, TagSuggestion::ChildId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TagSuggestion::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TagSuggestion {
+    Table,
+    #[sea_orm(iden = "parent_id")]
+    ParentId,
+    #[sea_orm(iden = "child_id")]
+    ChildId,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240225_224934_create_user::User;
+use crate::m20240227_020126_create_post::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostScore::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PostScore::PostId).integer().not_null())
+                    .col(ColumnDef::new(PostScore::UserId).integer().not_null())
+                    .col(ColumnDef::new(PostScore::Score).integer().not_null())
+                    .col(ColumnDef::new(PostScore::Time).timestamp().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PostScore::PostId)
+                            .col(PostScore::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_score_postid")
+                            .from(PostScore::Table, PostScore::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_score_userid")
+                            .from(PostScore::Table, PostScore::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostScore::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostScore {
+    Table,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    #[sea_orm(iden = "user_id")]
+    UserId,
+    Score,
+    Time,
+}
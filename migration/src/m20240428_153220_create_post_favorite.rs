@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240225_224934_create_user::User;
+use crate::m20240227_020126_create_post::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostFavorite::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PostFavorite::PostId).integer().not_null())
+                    .col(ColumnDef::new(PostFavorite::UserId).integer().not_null())
+                    .col(ColumnDef::new(PostFavorite::Time).timestamp().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PostFavorite::PostId)
+                            .col(PostFavorite::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_favorite_postid")
+                            .from(PostFavorite::Table, PostFavorite::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_favorite_userid")
+                            .from(PostFavorite::Table, PostFavorite::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostFavorite::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostFavorite {
+    Table,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    #[sea_orm(iden = "user_id")]
+    UserId,
+    Time,
+}
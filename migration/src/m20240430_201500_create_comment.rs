@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240225_224934_create_user::User;
+use crate::m20240227_020126_create_post::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Comment::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Comment::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Comment::PostId).integer().not_null())
+                    .col(ColumnDef::new(Comment::UserId).integer())
+                    .col(ColumnDef::new(Comment::CreationTime).timestamp().not_null())
+                    .col(ColumnDef::new(Comment::LastEditTime).timestamp())
+                    .col(ColumnDef::new(Comment::Text).text().not_null())
+                    .col(ColumnDef::new(Comment::Version).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_comment_postid")
+                            .from(Comment::Table, Comment::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_comment_userid")
+                            .from(Comment::Table, Comment::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Comment::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub(super) enum Comment {
+    Table,
+    Id,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    #[sea_orm(iden = "user_id")]
+    UserId,
+    #[sea_orm(iden = "creation_time")]
+    CreationTime,
+    #[sea_orm(iden = "last_edit_time")]
+    LastEditTime,
+    Text,
+    Version,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240225_224934_create_user::User;
+use crate::m20240430_201500_create_comment::Comment;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CommentScore::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(CommentScore::CommentId).integer().not_null())
+                    .col(ColumnDef::new(CommentScore::UserId).integer().not_null())
+                    .col(ColumnDef::new(CommentScore::Score).integer().not_null())
+                    .col(ColumnDef::new(CommentScore::Time).timestamp().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(CommentScore::CommentId)
+                            .col(CommentScore::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_comment_score_commentid")
+                            .from(CommentScore::Table, CommentScore::CommentId)
+                            .to(Comment::Table, Comment::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_comment_score_userid")
+                            .from(CommentScore::Table, CommentScore::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CommentScore::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CommentScore {
+    Table,
+    #[sea_orm(iden = "comment_id")]
+    CommentId,
+    #[sea_orm(iden = "user_id")]
+    UserId,
+    Score,
+    Time,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PoolCategory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PoolCategory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PoolCategory::Name)
+                            .string_len(32)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(PoolCategory::Color).string_len(32).not_null())
+                    .col(ColumnDef::new(PoolCategory::IsDefault).boolean().not_null())
+                    .col(ColumnDef::new(PoolCategory::Version).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        // Every pool needs a category
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(PoolCategory::Table)
+                    .columns([PoolCategory::Name, PoolCategory::Color, PoolCategory::IsDefault, PoolCategory::Version])
+                    .values_panic(["default".into(), "default".into(), true.into(), 1.into()])
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PoolCategory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub(super) enum PoolCategory {
+    Table,
+    Id,
+    Name,
+    Color,
+    #[sea_orm(iden = "default")]
+    IsDefault,
+    Version,
+}
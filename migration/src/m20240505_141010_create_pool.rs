@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240505_141000_create_pool_category::PoolCategory;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Pool::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Pool::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Pool::Category).string_len(32).not_null())
+                    .col(ColumnDef::new(Pool::Description).text())
+                    .col(ColumnDef::new(Pool::CreationTime).timestamp().not_null())
+                    .col(ColumnDef::new(Pool::LastEditTime).timestamp())
+                    .col(ColumnDef::new(Pool::Version).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_pool_category")
+                            .from(Pool::Table, Pool::Category)
+                            .to(PoolCategory::Table, PoolCategory::Name)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Pool::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub(super) enum Pool {
+    Table,
+    Id,
+    Category,
+    Description,
+    #[sea_orm(iden = "creation_time")]
+    CreationTime,
+    #[sea_orm(iden = "last_edit_time")]
+    LastEditTime,
+    Version,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240505_141010_create_pool::Pool;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PoolName::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PoolName::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PoolName::PoolId).integer().not_null())
+                    .col(
+                        ColumnDef::new(PoolName::Name)
+                            .string_len(256)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(PoolName::Ord).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_pool_name_poolid")
+                            .from(PoolName::Table, PoolName::PoolId)
+                            .to(Pool::Table, Pool::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PoolName::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PoolName {
+    Table,
+    Id,
+    #[sea_orm(iden = "pool_id")]
+    PoolId,
+    Name,
+    Ord,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240227_020126_create_post::Post;
+use crate::m20240505_141010_create_pool::Pool;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PoolPost::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PoolPost::PoolId).integer().not_null())
+                    .col(ColumnDef::new(PoolPost::PostId).integer().not_null())
+                    .col(ColumnDef::new(PoolPost::Ord).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PoolPost::PoolId)
+                            .col(PoolPost::PostId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_pool_post_poolid")
+                            .from(PoolPost::Table, PoolPost::PoolId)
+                            .to(Pool::Table, Pool::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_pool_post_postid")
+                            .from(PoolPost::Table, PoolPost::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PoolPost::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PoolPost {
+    Table,
+    #[sea_orm(iden = "pool_id")]
+    PoolId,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    Ord,
+}
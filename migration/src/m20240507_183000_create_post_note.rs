@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240227_020126_create_post::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostNote::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PostNote::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PostNote::PostId).integer().not_null())
+                    .col(ColumnDef::new(PostNote::Polygon).json().not_null())
+                    .col(ColumnDef::new(PostNote::Text).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_note_postid")
+                            .from(PostNote::Table, PostNote::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostNote::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostNote {
+    Table,
+    Id,
+    #[sea_orm(iden = "post_id")]
+    PostId,
+    Polygon,
+    Text,
+}
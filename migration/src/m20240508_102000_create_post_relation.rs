@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240227_020126_create_post::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostRelation::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PostRelation::ParentId).integer().not_null())
+                    .col(ColumnDef::new(PostRelation::ChildId).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PostRelation::ParentId)
+                            .col(PostRelation::ChildId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_relation_parentid")
+                            .from(PostRelation::Table, PostRelation::ParentId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_post_relation_childid")
+                            .from(PostRelation::Table, PostRelation::ChildId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostRelation::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostRelation {
+    Table,
+    #[sea_orm(iden = "parent_id")]
+    ParentId,
+    #[sea_orm(iden = "child_id")]
+    ChildId,
+}
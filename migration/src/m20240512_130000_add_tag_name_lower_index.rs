@@ -0,0 +1,22 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Names are looked up case-insensitively, so they have to be unique that way too
+        manager
+            .get_connection()
+            .execute_unprepared(r#"CREATE UNIQUE INDEX "idx_tag_name_lower" ON "tag_name" (lower("name"))"#)
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_tag_name_lower").to_owned())
+            .await
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::schemas::{pool_category, tag_category};
+
+/// Common part of tag and pool categories.
+pub trait Category {
+    fn name(&self) -> &str;
+    fn is_default(&self) -> bool;
+    fn into_answer(self, usages: i64) -> CategoryHttpAnswer;
+}
+
+impl Category for tag_category::Model {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn is_default(&self) -> bool {
+        self.is_default
+    }
+    fn into_answer(self, usages: i64) -> CategoryHttpAnswer {
+        CategoryHttpAnswer {
+            name: self.name,
+            version: self.version,
+            color: self.color,
+            usages,
+            order: Some(self.order),
+            default: self.is_default,
+        }
+    }
+}
+
+impl Category for pool_category::Model {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn is_default(&self) -> bool {
+        self.is_default
+    }
+    fn into_answer(self, usages: i64) -> CategoryHttpAnswer {
+        CategoryHttpAnswer {
+            name: self.name,
+            version: self.version,
+            color: self.color,
+            usages,
+            order: None,
+            default: self.is_default,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CategoryHttpAnswer {
+    pub name: String,
+    pub version: i32,
+    pub color: String,
+    pub usages: i64,
+    /// Only tag categories are ordered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+    pub default: bool,
+}
+
+#[derive(Serialize)]
+pub struct ListCategoriesHttpResponse {
+    pub results: Vec<CategoryHttpAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteCategoryHttpQuery {
+    pub version: i32,
+}
+
+/// `usages` maps category names to number of their tags or pools.
+pub fn category_answer(raw: impl Category, usages: &HashMap<String, i64>) -> CategoryHttpAnswer {
+    let count = usages.get(raw.name()).copied().unwrap_or_default();
+    raw.into_answer(count)
+}
+
+pub fn list_answer(raws: Vec<impl Category>, usages: &HashMap<String, i64>) -> ListCategoriesHttpResponse {
+    ListCategoriesHttpResponse { results: raws.into_iter().map(|raw| category_answer(raw, usages)).collect() }
+}
+
+/// Errors are messages for kind specific `ApiError`.
+pub fn validate_name(name_regex: &str, name: &str) -> Result<(), String> {
+    let regex = Regex::new(name_regex).expect("Invalid category name regex in config!");
+    if !regex.is_match(name) || name.len() > 32 {
+        return Err(format!("Name must satisfy regex {name_regex:?}."));
+    }
+    Ok(())
+}
+
+pub fn validate_color(color: &str) -> Result<(), String> {
+    let regex = Regex::new(r"^#?[0-9a-zA-Z]+$").unwrap();
+    if !regex.is_match(color) || color.len() > 32 {
+        return Err(format!("Color {color:?} is invalid."));
+    }
+    Ok(())
+}
+
+/// Default category and categories in use can't be deleted.
+/// `kind` is like "Tag category", `items` is what uses the category.
+pub fn check_deletable(raw: &impl Category, usages: &HashMap<String, i64>, kind: &str, items: &str) -> Result<(), String> {
+    if raw.is_default() {
+        return Err("Cannot delete the default category.".to_string());
+    }
+    if usages.get(raw.name()).is_some_and(|count| *count > 0) {
+        return Err(format!("{kind} has some usages and cannot be deleted. Please remove this category from relevant {items} first."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_colors() {
+        assert!(validate_name("^[a-z]+$", "meta").is_ok());
+        assert!(validate_name("^[a-z]+$", "Meta").is_err());
+        assert!(validate_name(".*", &"a".repeat(33)).is_err());
+        assert!(validate_color("#ff00ff").is_ok());
+        assert!(validate_color("red").is_ok());
+        assert!(validate_color("#ff 00").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::extract::{Json, Path, Query, State};
+use chrono::NaiveDateTime;
+use log::debug;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{fields::{Fields, FieldsParams, Sparse}, page::{PageParams, PagedResponse}, user::MicroUser},
+    db::schemas::comment,
+    error::{check_version, ApiError, ApiResult, AuthError},
+    func::{search::{parse_comment_query, CommentSort}, user::get_avatar_url},
+    AppState, CurrentUser, UserRank
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAnswer {
+    pub id: i32,
+    pub version: i32,
+    pub post_id: i32,
+    pub user: Option<MicroUser>,
+    pub text: String,
+    pub creation_time: NaiveDateTime,
+    pub last_edit_time: Option<NaiveDateTime>,
+    pub score: i64,
+    pub own_score: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCommentHttpQuery {
+    pub text: String,
+    pub post_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommentHttpQuery {
+    pub version: i32,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteCommentHttpQuery {
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RateCommentHttpQuery {
+    pub score: i32,
+}
+
+/// Builds representations of comments, fetching authors and scores at once.
+pub async fn comment_answers(state: &AppState, caller: &CurrentUser, raw_comments: Vec<comment::Model>) -> ApiResult<Vec<CommentAnswer>> {
+    let comment_ids: Vec<i32> = raw_comments.iter().map(|raw| raw.id).collect();
+    let user_ids: Vec<i32> = raw_comments.iter().filter_map(|raw| raw.user_id).collect();
+    let users: HashMap<i32, MicroUser> = state.db.get_users_by_ids(&user_ids).await?
+        .into_iter()
+        .map(|raw_user| (raw_user.id, MicroUser { avatar_url: get_avatar_url(&state.config.thumbnails, &raw_user), name: raw_user.name }))
+        .collect();
+    let scores = state.db.get_comment_scores(&comment_ids).await?;
+    let own_scores = match caller.id() {
+        Some(user_id) => state.db.get_own_comment_scores(&comment_ids, user_id).await?,
+        None => HashMap::new(),
+    };
+    Ok(raw_comments.into_iter().map(|raw| CommentAnswer {
+        id: raw.id,
+        version: raw.version,
+        post_id: raw.post_id,
+        user: raw.user_id.and_then(|user_id| users.get(&user_id).cloned()),
+        text: raw.text,
+        creation_time: raw.creation_time,
+        last_edit_time: raw.last_edit_time,
+        score: scores.get(&raw.id).copied().unwrap_or_default(),
+        own_score: own_scores.get(&raw.id).copied().unwrap_or_default(),
+    }).collect())
+}
+
+async fn comment_answer(state: &AppState, caller: &CurrentUser, raw: comment::Model) -> ApiResult<CommentAnswer> {
+    Ok(comment_answers(state, caller, vec![raw]).await?.remove(0))
+}
+
+/// Own privilege applies to comments of caller, any privilege to the rest.
+fn require_own_or_any(caller: &CurrentUser, raw: &comment::Model, own: UserRank, any: UserRank) -> ApiResult<()> {
+    let privilege = match caller.id() {
+        Some(user_id) if raw.user_id == Some(user_id) => own,
+        _ => any,
+    };
+    Ok(caller.require(privilege)?)
+}
+
+fn validate_text(text: &str) -> ApiResult<()> {
+    if text.trim().is_empty() {
+        return Err(ApiError::EmptyCommentText("Comment text cannot be empty.".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn list_comments(
+    caller: CurrentUser,
+    Query(params): Query<PageParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<PagedResponse<Sparse<CommentAnswer>>>> {
+    caller.require(state.config.privileges.comments_list)?;
+    let search = parse_comment_query(params.query.as_deref().unwrap_or_default()).map_err(ApiError::Search)?;
+    if params.after.is_some() && search.sort != CommentSort::Id {
+        return Err(ApiError::Search("Keyset pagination works only with sorting by id.".to_string()));
+    }
+    let page = params.page();
+    let raw_comments = state.db.search_comments(&search, page).await?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let results = comment_answers(&state, &caller, raw_comments.results).await?
+        .into_iter()
+        .map(|answer| fields.sparse(answer))
+        .collect();
+    Ok(Json(PagedResponse::new(params.query, page, raw_comments.total, results)))
+}
+
+pub async fn get_comment(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    Query(params): Query<FieldsParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Sparse<CommentAnswer>>> {
+    caller.require(state.config.privileges.comments_view)?;
+    let raw = state.db.get_comment_by_id(id).await?;
+    let fields = Fields::parse(params.fields.as_deref());
+    Ok(Json(fields.sparse(comment_answer(&state, &caller, raw).await?)))
+}
+
+pub async fn create_comment(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreateCommentHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CommentAnswer>> {
+    debug!("Trying to create comment with params: {params:?}");
+    caller.require(state.config.privileges.comments_create)?;
+    validate_text(&params.text)?;
+    let raw_post = state.db.get_post_by_id(params.post_id).await?;
+    let form_data = comment::ActiveModel {
+        post_id: Set(raw_post.id),
+        user_id: Set(caller.id()),
+        text: Set(params.text),
+        ..Default::default()
+    };
+    let raw = state.db.create_comment(form_data).await?;
+    Ok(Json(comment_answer(&state, &caller, raw).await?))
+}
+
+pub async fn update_comment(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdateCommentHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CommentAnswer>> {
+    debug!("Trying to update comment {id}");
+    let privileges = &state.config.privileges;
+    let raw = state.db.get_comment_by_id(id).await?;
+    require_own_or_any(&caller, &raw, privileges.comments_edit_own, privileges.comments_edit_any)?;
+    check_version(raw.version, params.version)?;
+    validate_text(&params.text)?;
+
+    let mut form_data: comment::ActiveModel = raw.clone().into();
+    form_data.text = Set(params.text);
+    let raw = state.db.update_comment(id, params.version, form_data).await?;
+    Ok(Json(comment_answer(&state, &caller, raw).await?))
+}
+
+pub async fn delete_comment(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeleteCommentHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    let privileges = &state.config.privileges;
+    let raw = state.db.get_comment_by_id(id).await?;
+    require_own_or_any(&caller, &raw, privileges.comments_delete_own, privileges.comments_delete_any)?;
+    check_version(raw.version, params.version)?;
+    state.db.delete_comment(id).await?;
+    debug!("Comment {id} deleted!");
+    Ok("{}")
+}
+
+pub async fn rate_comment(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<RateCommentHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CommentAnswer>> {
+    caller.require(state.config.privileges.comments_score)?;
+    if !(-1..=1).contains(&params.score) {
+        return Err(ApiError::InvalidScoreValue(format!("Score {} is invalid. Valid scores are -1, 0 and 1.", params.score)));
+    }
+    let user_id = caller.id().ok_or(AuthError::InsufficientPrivileges)?;
+    let raw = state.db.get_comment_by_id(id).await?;
+    state.db.set_comment_score(raw.id, user_id, params.score).await?;
+    Ok(Json(comment_answer(&state, &caller, raw).await?))
+}
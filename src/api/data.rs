@@ -1,10 +1,12 @@
-use axum::{extract::{Multipart, State}, routing::get_service, Json, Router};
-use serde::Serialize;
+use axum::{
+    async_trait, body::Bytes, extract::{FromRequest, Multipart, Request, State}, http::header::CONTENT_TYPE, routing::get_service, Json, Router
+};
+use serde::{de::DeserializeOwned, Serialize};
 use tower_http::services::ServeDir;
 use log::{debug, info};
-use std::{fs, sync::Arc};
+use std::{collections::HashMap, fs, sync::Arc};
 
-use crate::{data::DATA, error::{ApiError, ApiResult}, AppState};
+use crate::{data::DATA, error::{ApiError, ApiResult}, AppState, CurrentUser};
 
 pub fn data_static() -> Router {
     Router::new().nest_service("/", get_service(ServeDir::new(DATA)))
@@ -38,23 +40,117 @@ fn to_upload_error(_: anyhow::Error) -> ApiError {
     ApiError::Uploads
 }
 
-pub async fn upload(State(state): State<Arc<AppState>>, mut multipart: Multipart) -> ApiResult<Json<UploadResponse>> {
+/// File sent in multipart field, `content_type` is as declared by client.
+pub struct FilePart {
+    pub content_type: String,
+    pub data: Bytes,
+}
+
+/// Files sent alongside JSON metadata, keyed by multipart field name.
+pub type Files = HashMap<String, FilePart>;
+
+/// Request body which is either plain JSON, or multipart form with JSON in
+/// `metadata` field and files in the other fields, as szurubooru client sends it.
+pub struct JsonOrMultipart<T>(pub T, pub Files);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for JsonOrMultipart<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_multipart = req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+        if !is_multipart {
+            let Json(params) = Json::<T>::from_request(req, state).await
+                .map_err(|e| ApiError::InvalidParameter(e.body_text()))?;
+            return Ok(Self(params, Files::new()));
+        }
+        let mut multipart = Multipart::from_request(req, state).await
+            .map_err(|e| ApiError::InvalidParameter(e.body_text()))?;
+        let mut metadata = None;
+        let mut files = Files::new();
+        while let Some(field) = multipart.next_field().await.map_err(|e| ApiError::InvalidParameter(e.body_text()))? {
+            let name = field.name().unwrap_or_default().to_string();
+            let content_type = field.content_type().unwrap_or_default().to_string();
+            let data = field.bytes().await.map_err(|e| ApiError::InvalidParameter(e.body_text()))?;
+            if name == "metadata" {
+                metadata = Some(serde_json::from_slice(&data).map_err(|e| ApiError::InvalidParameter(e.to_string()))?);
+            } else {
+                files.insert(name, FilePart { content_type, data });
+            }
+        }
+        let params = metadata.ok_or_else(|| ApiError::MissingRequiredParameter("metadata".to_string()))?;
+        Ok(Self(params, files))
+    }
+}
+
+/// File sent in multipart field or referenced by upload token.
+pub struct UploadedFile {
+    pub content: Vec<u8>,
+    pub content_type: String,
+    /// Upload the content was read from, kept until `release`
+    token: Option<String>,
+}
+
+impl UploadedFile {
+    /// Removes upload the content was read from. Called only after the request
+    /// succeeded, so client can retry with the same token otherwise.
+    pub fn release(self, state: &AppState) -> ApiResult<()> {
+        let Some(token) = self.token else {
+            return Ok(());
+        };
+        if let Some(upload) = state.uploads.lock().expect("Uploads mutex was poisoned!").get_and_remove(&token) {
+            fs::remove_file(upload.path())?;
+        }
+        Ok(())
+    }
+}
+
+/// Content of file sent in multipart field `name`, or of upload referenced by `token`.
+pub fn read_file(state: &AppState, files: &mut Files, name: &str, token: Option<&str>) -> ApiResult<Option<UploadedFile>> {
+    if let Some(part) = files.remove(name) {
+        return Ok(Some(UploadedFile { content: part.data.to_vec(), content_type: part.content_type, token: None }));
+    }
+    let Some(token) = token else {
+        return Ok(None);
+    };
+    let upload = state.uploads.lock().expect("Uploads mutex was poisoned!").get(token)
+        .ok_or_else(|| ApiError::MissingRequiredFile(format!("Uploaded file {token:?} not found.")))?;
+    let content = fs::read(upload.path())?;
+    Ok(Some(UploadedFile { content, content_type: upload.content_type, token: Some(token.to_string()) }))
+}
+
+pub async fn upload(caller: CurrentUser, State(state): State<Arc<AppState>>, mut multipart: Multipart) -> ApiResult<Json<UploadResponse>> {
+    caller.require(state.config.privileges.uploads_create)?;
     let mut token: Option<String> = None;
     while let Some(field) = multipart.next_field().await.unwrap() {
         let name = field.name().unwrap().to_string();
         // debug!("Multipart: {:?} {:?} {:?} {:?}", field.content_type(), field.file_name(), field.name(), field.headers());
-        if name == "content".to_string() {
+        if name == "content" {
             let filename = field.file_name().unwrap().to_owned();
             let content_type = field.content_type().unwrap().to_owned();
             let (_, extension) = filename.split_once('.').expect("Damaged file");
             let data = field.bytes().await.unwrap();
+            let mime_type = image::guess_format(&data)
+                .map(|format| format.to_mime_type().to_string())
+                .unwrap_or(content_type.clone());
+            let max_size = state.config.limits.max_upload_size_for(&mime_type);
+            if data.len() > max_size {
+                return Err(ApiError::Validation(format!("Files of type {mime_type:?} can't be larger than {max_size} bytes.")));
+            }
             token = Some(state.uploads.lock().expect("Uploads mutex was poisoned!").add(extension, &content_type, data).map_err(to_upload_error)?);
         }
     }
     match token {
         Some(token) => {
             info!("Responding token: {token}");
-            return Ok(Json(UploadResponse { token }))
+            Ok(Json(UploadResponse { token }))
         },
         None => {
             panic!("DOESN'T HAVE CONTENT!")
@@ -0,0 +1,96 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serde::{ser::{Error, SerializeMap}, Deserialize, Serialize, Serializer};
+
+/// `fields` query parameter, shared by endpoints without own params.
+#[derive(Debug, Deserialize, Default)]
+pub struct FieldsParams {
+    pub fields: Option<String>,
+}
+
+/// Parsed `fields` parameter. Nothing requested means every field.
+#[derive(Debug, Clone, Default)]
+pub struct Fields(Option<Arc<HashSet<String>>>);
+
+impl Fields {
+    pub fn parse(raw: Option<&str>) -> Self {
+        let fields: HashSet<String> = raw
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect();
+        if fields.is_empty() {
+            Self(None)
+        } else {
+            Self(Some(Arc::new(fields)))
+        }
+    }
+    /// Whether field is requested, used to skip unneeded queries.
+    pub fn has(&self, field: &str) -> bool {
+        self.0.as_ref().is_none_or(|fields| fields.contains(field))
+    }
+    pub fn sparse<T>(&self, value: T) -> Sparse<T> {
+        Sparse { value, fields: self.clone() }
+    }
+}
+
+/// Serializes only requested keys of `value`.
+pub struct Sparse<T> {
+    value: T,
+    fields: Fields,
+}
+
+impl<T: Serialize> Serialize for Sparse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = &self.fields.0 else {
+            return self.value.serialize(serializer);
+        };
+        match serde_json::to_value(&self.value).map_err(S::Error::custom)? {
+            serde_json::Value::Object(object) => {
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in object.iter().filter(|(key, _)| fields.contains(*key)) {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Answer {
+        id: i32,
+        name: String,
+    }
+
+    fn sparse_json(raw: Option<&str>) -> String {
+        let answer = Answer { id: 1, name: "x".to_string() };
+        serde_json::to_string(&Fields::parse(raw).sparse(answer)).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        for raw in [None, Some(""), Some(" , ,")] {
+            let fields = Fields::parse(raw);
+            assert!(fields.has("id") && fields.has("anything"));
+        }
+        let fields = Fields::parse(Some("id, name"));
+        assert!(fields.has("id") && fields.has("name"));
+        assert!(!fields.has("unknown"));
+    }
+    #[test]
+    fn sparse() {
+        assert_eq!(sparse_json(None), r#"{"id":1,"name":"x"}"#);
+        assert_eq!(sparse_json(Some("")), r#"{"id":1,"name":"x"}"#);
+        assert_eq!(sparse_json(Some("name")), r#"{"name":"x"}"#);
+        assert_eq!(sparse_json(Some("name,unknown")), r#"{"name":"x"}"#);
+        assert_eq!(sparse_json(Some("unknown")), "{}");
+    }
+}
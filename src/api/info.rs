@@ -1,5 +1,5 @@
 use crate::{
-    config::Privileges,
+    config::{Limits, Privileges, Tokens},
     AppState, Config, UserRank,
 };
 use axum::{extract::State, Json};
@@ -48,6 +48,10 @@ pub struct FrontendConfig {
     can_send_mails: bool,
     #[serde(rename = "privileges")]
     privileges: Privileges,
+    #[serde(rename = "tokens")]
+    tokens: Tokens,
+    #[serde(rename = "limits")]
+    limits: Limits,
 }
 
 impl FrontendConfig {
@@ -63,6 +67,8 @@ impl FrontendConfig {
             contact_email: config.contact_email,
             can_send_mails: config.smtp.enabled,
             privileges: config.privileges,
+            tokens: config.tokens,
+            limits: config.limits,
         }
     }
 }
@@ -1,6 +1,14 @@
+pub mod category;
+pub mod comment;
 pub mod data;
+pub mod fields;
 pub mod info;
+pub mod page;
+pub mod pool;
+pub mod pool_category;
 pub mod post;
+pub mod tag;
+pub mod tag_category;
 pub mod test;
 pub mod user;
 pub mod usertoken;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::repository::PageRequest;
+
+/// Query parameters shared by every listing.
+#[derive(Debug, Deserialize, Default)]
+pub struct PageParams {
+    pub query: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+    /// Id of the last row of previous page, enables keyset pagination
+    pub after: Option<i32>,
+    pub fields: Option<String>,
+}
+
+impl PageParams {
+    pub fn page(&self) -> PageRequest {
+        PageRequest::new(self.offset, self.limit, self.after)
+    }
+}
+
+/// Szurubooru listing envelope.
+#[derive(Serialize)]
+pub struct PagedResponse<T> {
+    pub query: String,
+    pub offset: u64,
+    pub limit: u64,
+    pub total: u64,
+    pub results: Vec<T>,
+}
+
+impl<T> PagedResponse<T> {
+    pub fn new(query: Option<String>, request: PageRequest, total: u64, results: Vec<T>) -> Self {
+        Self {
+            query: query.unwrap_or_default(),
+            offset: request.offset,
+            limit: request.limit,
+            total,
+            results,
+        }
+    }
+}
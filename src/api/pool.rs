@@ -0,0 +1,305 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::extract::{Json, Path, Query, State};
+use chrono::NaiveDateTime;
+use log::debug;
+use regex::Regex;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{fields::{Fields, FieldsParams, Sparse}, page::{PageParams, PagedResponse}, post::{micro_post, model::MicroPost}},
+    db::{errors::GetPoolCategoryError, schemas::{pool, snapshot}}, error::{check_version, ApiError, ApiResult}, AppState, CurrentUser
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MicroPool {
+    pub id: i32,
+    pub names: Vec<String>,
+    pub category: String,
+    pub description: Option<String>,
+    pub post_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolHttpAnswer {
+    pub version: i32,
+    pub id: i32,
+    pub names: Vec<String>,
+    pub category: String,
+    pub posts: Vec<MicroPost>,
+    pub creation_time: NaiveDateTime,
+    pub last_edit_time: Option<NaiveDateTime>,
+    pub post_count: i64,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolHttpQuery {
+    pub names: Vec<String>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub posts: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePoolHttpQuery {
+    pub version: i32,
+    pub names: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub posts: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletePoolHttpQuery {
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePoolsHttpQuery {
+    pub remove_version: i32,
+    pub remove: u64,
+    pub merge_to_version: i32,
+    pub merge_to: u64,
+}
+
+/// Builds short pool representations, keeping order of `pools`.
+pub async fn micro_pools(state: &AppState, pools: &[pool::Model]) -> ApiResult<Vec<MicroPool>> {
+    let ids: Vec<i32> = pools.iter().map(|pool| pool.id).collect();
+    let mut names = state.db.get_pool_names(&ids).await?;
+    let posts = state.db.get_pool_posts(&ids).await?;
+    Ok(pools.iter().map(|pool| MicroPool {
+        id: pool.id,
+        names: names.remove(&pool.id).unwrap_or_default(),
+        category: pool.category.clone(),
+        description: pool.description.clone(),
+        post_count: posts.get(&pool.id).map(Vec::len).unwrap_or_default() as i64,
+    }).collect())
+}
+
+/// Builds full pool representations, names and posts of all `pools` are loaded at once.
+async fn pool_answers(state: &AppState, pools: Vec<pool::Model>) -> ApiResult<Vec<PoolHttpAnswer>> {
+    let ids: Vec<i32> = pools.iter().map(|pool| pool.id).collect();
+    let mut names = state.db.get_pool_names(&ids).await?;
+    let mut posts = state.db.get_pool_posts(&ids).await?;
+    Ok(pools.into_iter().map(|pool| {
+        let post_ids = posts.remove(&pool.id).unwrap_or_default();
+        PoolHttpAnswer {
+            version: pool.version,
+            id: pool.id,
+            names: names.remove(&pool.id).unwrap_or_default(),
+            category: pool.category,
+            post_count: post_ids.len() as i64,
+            posts: post_ids.into_iter().map(|post_id| micro_post(state, post_id)).collect(),
+            creation_time: pool.creation_time,
+            last_edit_time: pool.last_edit_time,
+            description: pool.description,
+        }
+    }).collect())
+}
+
+async fn pool_answer(state: &AppState, pool: pool::Model) -> ApiResult<PoolHttpAnswer> {
+    Ok(pool_answers(state, vec![pool]).await?.remove(0))
+}
+
+/// Checks names against `pool_name_regex` and drops case-insensitive duplicates.
+fn validate_pool_names(state: &AppState, names: &[String]) -> ApiResult<Vec<String>> {
+    let regex = Regex::new(&state.config.pool_name_regex).expect("Invalid pool_name_regex in config!");
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        if !regex.is_match(name) || name.len() > 256 {
+            return Err(ApiError::InvalidPoolName(format!("Pool name {name:?} must satisfy regex {:?}.", state.config.pool_name_regex)));
+        }
+        if seen.insert(name.to_lowercase()) {
+            result.push(name.to_string());
+        }
+    }
+    if result.is_empty() {
+        return Err(ApiError::InvalidPoolName("At least one name must be specified.".to_string()));
+    }
+    Ok(result)
+}
+
+/// Returns canonical name of existing category.
+async fn validate_pool_category(state: &AppState, category: &str) -> ApiResult<String> {
+    match state.db.get_pool_category_by_name(category).await {
+        Ok(category) => Ok(category.name),
+        Err(GetPoolCategoryError::PoolCategoryNotFound { .. }) => Err(ApiError::InvalidPoolCategory(format!("Category {category:?} is invalid."))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Rejects names already taken by pools other than `pool_id`.
+async fn check_names_available(state: &AppState, names: &[String], pool_id: Option<i32>) -> ApiResult<()> {
+    let taken = state.db.get_pools_by_names(names).await?;
+    if let Some((name, _)) = taken.iter().find(|(_, pool)| Some(pool.id) != pool_id) {
+        return Err(ApiError::PoolAlreadyExists(format!("Pool {:?} already exists.", name.name)));
+    }
+    Ok(())
+}
+
+/// Rejects repeated and missing posts, order of `posts` is kept.
+async fn validate_pool_posts(state: &AppState, posts: &[u64]) -> ApiResult<Vec<i32>> {
+    let post_ids: Vec<i32> = posts
+        .iter()
+        .map(|id| i32::try_from(*id).map_err(|_| ApiError::InvalidPoolNonexistentPost(format!("Post {id} does not exist."))))
+        .collect::<Result<_, _>>()?;
+    let mut seen = HashSet::new();
+    if let Some(id) = post_ids.iter().find(|id| !seen.insert(**id)) {
+        return Err(ApiError::InvalidPoolDuplicate(format!("Post {id} occurs more than once.")));
+    }
+    let existing: HashSet<i32> = state.db.get_posts_by_ids(&post_ids).await?.into_iter().map(|post| post.id).collect();
+    if let Some(id) = post_ids.iter().find(|id| !existing.contains(id)) {
+        return Err(ApiError::InvalidPoolNonexistentPost(format!("Post {id} does not exist.")));
+    }
+    Ok(post_ids)
+}
+
+pub async fn list_pools(
+    caller: CurrentUser,
+    Query(params): Query<PageParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<PagedResponse<Sparse<PoolHttpAnswer>>>> {
+    caller.require(state.config.privileges.pools_list)?;
+    // Only name filtering is supported, `*` works as wildcard
+    let name_pattern = params.query
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .find(|token| !token.contains(':'));
+    let page = params.page();
+
+    let raw_pools = state.db.get_pools_in_page(name_pattern, page).await?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let results = pool_answers(&state, raw_pools.results).await?
+        .into_iter()
+        .map(|answer| fields.sparse(answer))
+        .collect();
+    Ok(Json(PagedResponse::new(params.query, page, raw_pools.total, results)))
+}
+
+pub async fn get_pool(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    Query(params): Query<FieldsParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Sparse<PoolHttpAnswer>>> {
+    caller.require(state.config.privileges.pools_view)?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let pool = state.db.get_pool_by_id(id).await?;
+    Ok(Json(fields.sparse(pool_answer(&state, pool).await?)))
+}
+
+pub async fn create_pool(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreatePoolHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<PoolHttpAnswer>> {
+    debug!("Trying to create new pool with params: {params:?}");
+    caller.require(state.config.privileges.pools_create)?;
+    let names = validate_pool_names(&state, &params.names)?;
+    check_names_available(&state, &names, None).await?;
+    let category = match params.category {
+        Some(category) => validate_pool_category(&state, &category).await?,
+        None => state.db.get_default_pool_category().await?.name,
+    };
+    let post_ids = validate_pool_posts(&state, &params.posts).await?;
+
+    let form_data = pool::ActiveModel {
+        category: Set(category),
+        description: Set(params.description),
+        ..Default::default()
+    };
+    let pool = state.db.create_pool(&names, &post_ids, form_data).await?;
+    Ok(Json(pool_answer(&state, pool).await?))
+}
+
+pub async fn update_pool(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdatePoolHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<PoolHttpAnswer>> {
+    debug!("Trying to update pool {id} with params: {params:?}");
+    let privileges = &state.config.privileges;
+    let pool = state.db.get_pool_by_id(id).await?;
+    check_version(pool.version, params.version)?;
+
+    let mut names = None;
+    if let Some(new_names) = params.names {
+        caller.require(privileges.pools_edit_names)?;
+        let new_names = validate_pool_names(&state, &new_names)?;
+        check_names_available(&state, &new_names, Some(pool.id)).await?;
+        names = Some(new_names);
+    }
+    let mut form_data: pool::ActiveModel = pool.clone().into();
+    if let Some(category) = params.category {
+        caller.require(privileges.pools_edit_category)?;
+        form_data.category = Set(validate_pool_category(&state, &category).await?);
+    }
+    if let Some(description) = params.description {
+        caller.require(privileges.pools_edit_description)?;
+        form_data.description = Set(Some(description));
+    }
+    let mut post_ids = None;
+    if let Some(posts) = params.posts {
+        caller.require(privileges.pools_edit_posts)?;
+        post_ids = Some(validate_pool_posts(&state, &posts).await?);
+    }
+
+    let pool = state.db.update_pool(id, params.version, form_data, names.as_deref(), post_ids.as_deref()).await?;
+    Ok(Json(pool_answer(&state, pool).await?))
+}
+
+pub async fn delete_pool(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeletePoolHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    caller.require(state.config.privileges.pools_delete)?;
+    let pool = state.db.get_pool_by_id(id).await?;
+    check_version(pool.version, params.version)?;
+    state.db.delete_pool(id).await?;
+    debug!("Pool {id} deleted!");
+    Ok("{}")
+}
+
+pub async fn merge_pools(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<MergePoolsHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<PoolHttpAnswer>> {
+    debug!("Trying to merge pools with params: {params:?}");
+    caller.require(state.config.privileges.pools_merge)?;
+    let source = state.db.get_pool_by_id(params.remove).await?;
+    let target = state.db.get_pool_by_id(params.merge_to).await?;
+    check_version(source.version, params.remove_version)?;
+    check_version(target.version, params.merge_to_version)?;
+    if source.id == target.id {
+        return Err(ApiError::InvalidPoolRelation("Cannot merge pool with itself.".to_string()));
+    }
+
+    let source_name = state.db.get_pool_names(&[source.id]).await?
+        .remove(&source.id)
+        .and_then(|names| names.into_iter().next())
+        .unwrap_or_default();
+    let snapshot = snapshot::ActiveModel {
+        resource_type: Set("pool".to_string()),
+        operation: Set("merged".to_string()),
+        user_id: Set(caller.id()),
+        data: Set(Some(serde_json::to_vec(&("pool", target.id)).expect("Can't serialize snapshot"))),
+        resource_name: Set(source_name),
+        resource_pkey: Set(source.id),
+        ..Default::default()
+    };
+    let pool = state.db.merge_pools(source.id, target.id, snapshot).await?;
+    debug!("Pool {} merged into {}!", source.id, target.id);
+    Ok(Json(pool_answer(&state, pool).await?))
+}
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::extract::{Json, Path, State};
+use log::debug;
+use sea_orm::Set;
+use serde::Deserialize;
+
+use crate::{
+    api::category::{category_answer, check_deletable, list_answer, validate_color, validate_name, CategoryHttpAnswer, DeleteCategoryHttpQuery, ListCategoriesHttpResponse},
+    db::{errors::GetPoolCategoryError, schemas::pool_category}, error::{check_version, ApiError, ApiResult}, AppState, CurrentUser
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolCategoryHttpQuery {
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePoolCategoryHttpQuery {
+    pub version: i32,
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Rejects name taken by category other than `id`.
+async fn check_name_available(state: &AppState, name: &str, id: Option<i32>) -> ApiResult<()> {
+    match state.db.get_pool_category_by_name(name).await {
+        Ok(existing) if Some(existing.id) != id => Err(ApiError::PoolCategoryAlreadyExists(format!("Pool category {name:?} already exists."))),
+        Ok(_) | Err(GetPoolCategoryError::PoolCategoryNotFound { .. }) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn list_pool_categories(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<ListCategoriesHttpResponse>> {
+    caller.require(state.config.privileges.pool_categories_list)?;
+    let usages = state.db.get_pool_category_usages().await?;
+    Ok(Json(list_answer(state.db.get_pool_categories().await?, &usages)))
+}
+
+pub async fn get_pool_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    caller.require(state.config.privileges.pool_categories_view)?;
+    let raw = state.db.get_pool_category_by_name(&name).await?;
+    Ok(Json(category_answer(raw, &state.db.get_pool_category_usages().await?)))
+}
+
+pub async fn create_pool_category(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreatePoolCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    debug!("Trying to create new pool category with params: {params:?}");
+    caller.require(state.config.privileges.pool_categories_create)?;
+    validate_name(&state.config.pool_category_name_regex, &params.name).map_err(ApiError::InvalidPoolCategoryName)?;
+    validate_color(&params.color).map_err(ApiError::InvalidPoolCategoryColor)?;
+    check_name_available(&state, &params.name, None).await?;
+    let form_data = pool_category::ActiveModel {
+        name: Set(params.name),
+        color: Set(params.color),
+        ..Default::default()
+    };
+    let raw = state.db.create_pool_category(form_data).await?;
+    Ok(Json(category_answer(raw, &HashMap::new())))
+}
+
+pub async fn update_pool_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdatePoolCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    debug!("Trying to update pool category {name} with params: {params:?}");
+    let privileges = &state.config.privileges;
+    let raw = state.db.get_pool_category_by_name(&name).await?;
+    check_version(raw.version, params.version)?;
+
+    let mut form_data: pool_category::ActiveModel = raw.clone().into();
+    if let Some(new_name) = params.name {
+        caller.require(privileges.pool_categories_edit_name)?;
+        validate_name(&state.config.pool_category_name_regex, &new_name).map_err(ApiError::InvalidPoolCategoryName)?;
+        check_name_available(&state, &new_name, Some(raw.id)).await?;
+        form_data.name = Set(new_name);
+    }
+    if let Some(color) = params.color {
+        caller.require(privileges.pool_categories_edit_color)?;
+        validate_color(&color).map_err(ApiError::InvalidPoolCategoryColor)?;
+        form_data.color = Set(color);
+    }
+
+    let raw = state.db.update_pool_category(raw.id as u64, params.version, form_data).await?;
+    Ok(Json(category_answer(raw, &state.db.get_pool_category_usages().await?)))
+}
+
+pub async fn set_default_pool_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    caller.require(state.config.privileges.pool_categories_set_default)?;
+    let raw = state.db.get_pool_category_by_name(&name).await?;
+    let raw = state.db.set_default_pool_category(raw.id as u64).await?;
+    Ok(Json(category_answer(raw, &state.db.get_pool_category_usages().await?)))
+}
+
+pub async fn delete_pool_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeleteCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    caller.require(state.config.privileges.pool_categories_delete)?;
+    let raw = state.db.get_pool_category_by_name(&name).await?;
+    check_version(raw.version, params.version)?;
+    let usages = state.db.get_pool_category_usages().await?;
+    check_deletable(&raw, &usages, "Pool category", "pools").map_err(ApiError::PoolCategoryIsInUse)?;
+    state.db.delete_pool_category(raw.id as u64).await?;
+    debug!("Pool category {name} deleted!");
+    Ok("{}")
+}
@@ -1,13 +1,14 @@
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
 
-#[derive(Serialize, Deserialize)]
-pub struct ListOfPostsAnswer {
-    pub query: String,
-    pub offset: u64,
-    pub limit: u64,
-    pub total: u64,
-    pub results: Vec<MiniPost>,
+use crate::api::{comment::CommentAnswer, pool::MicroPool, tag::MicroTag};
+
+/// Post reference inside other resources.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MicroPost {
+    pub id: i32,
+    pub thumbnail_url: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -17,10 +18,10 @@ pub struct MiniPost {
     pub thumbnail_url: String,
     pub r#type: String,
     pub safety: String,
-    pub score: i32,
-    pub favorite_count: i32,
-    pub comment_count: i32,
-    pub tags: Vec<()>, // Vec<Tag>
+    pub score: i64,
+    pub favorite_count: i64,
+    pub comment_count: i64,
+    pub tags: Vec<MicroTag>,
     pub version: i32,
 }
 
@@ -28,10 +29,10 @@ impl MiniPost {
     pub fn from_model(
             model: &crate::db::schemas::post::Model,
             thumbnail_url: String,
-            score: i32,
-            favorite_count: i32,
-            comment_count: i32,
-            tags: Vec<()> // TODO: Vec<Tag>
+            score: i64,
+            favorite_count: i64,
+            comment_count: i64,
+            tags: Vec<MicroTag>
         ) -> Self {
         Self {
             id: model.id,
@@ -75,8 +76,8 @@ pub struct PostAnswer {
     #[serde(rename = "thumbnailUrl")]
     pub thumbnail_url: String,
     pub flags: Vec<String>,
-    pub tags: Vec<()>, // Vec<Tag>
-    pub relations: Vec<()>,
+    pub tags: Vec<MicroTag>,
+    pub relations: Vec<MicroPost>,
     pub user: Option<User>,
     pub score: i64,
     #[serde(rename = "ownScore")]
@@ -98,21 +99,21 @@ pub struct PostAnswer {
     #[serde(rename = "lastFeatureTime")]
     pub last_feature_time: Option<()>,
     #[serde(rename = "favoritedBy")]
-    pub favorited_by: Vec<()>,
+    pub favorited_by: Vec<User>,
     #[serde(rename = "hasCustomThumbnail")]
     pub has_custom_thumbnail: bool,
-    pub notes: Vec<()>,
-    pub comments: Vec<()>,
-    pub pools: Vec<()>,
+    pub notes: Vec<PostNote>,
+    pub comments: Vec<CommentAnswer>,
+    pub pools: Vec<MicroPool>,
 }
 
 
-// #[derive(Serialize, Deserialize)]
-// pub struct Tag {
-//     pub names: Vec<String>,
-//     pub category: String,
-//     pub usages: i64,
-// }
+/// Note polygon points are relative to post size.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PostNote {
+    pub polygon: Vec<Vec<f64>>,
+    pub text: String,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct User {
@@ -122,11 +123,38 @@ pub struct User {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct PostsParams {
-    pub query: String,
-    pub offset: Option<u64>,
-    pub limit: u64,
-    pub fields: String,
+#[serde(rename_all = "camelCase")]
+pub struct CreatePostQuery {
+    pub content_token: String,
+    pub safety: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub relations: Vec<u64>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub anonymous: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePostQuery {
+    pub version: i32,
+    pub tags: Option<Vec<String>>,
+    pub safety: Option<String>,
+    pub source: Option<String>,
+    pub relations: Option<Vec<u64>>,
+    pub notes: Option<Vec<PostNote>>,
+    pub flags: Option<Vec<String>>,
+    pub content_token: Option<String>,
+    pub thumbnail_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RatePostQuery {
+    pub score: i32,
 }
 
 #[derive(Deserialize)]
@@ -1,66 +1,438 @@
-use std::sync::Arc;
+use std::{fs, io::Cursor, path::Path as FsPath, sync::Arc};
 use axum::{
     extract::{Path, Query, State},
+    http::header::{HeaderName, CONTENT_TYPE},
     Json,
 };
-use log::debug;
+use image::ImageReader;
+use log::{debug, info, warn};
+use sea_orm::Set;
 
 use crate::{
-    error::{ApiError, ApiResult}, AppState, func::post::*
+    db::{repository::{Page, PostRelated}, schemas::{post, post_note}},
+    api::{comment::comment_answers, data::{read_file, JsonOrMultipart}, fields::{Fields, FieldsParams, Sparse}, page::{PageParams, PagedResponse}, pool::micro_pools, tag::{micro_tags, plan_post_tags}},
+    error::{check_version, ApiError, ApiResult, AuthError}, AppState, CurrentUser, func::{post::*, search::{parse_post_query, PostSort}, thumbnail::{encode_custom_post_thumbnail, generate_post_thumbnail}, user::get_avatar_url}
 };
 use super::model::*;
+use crate::api::tag::MicroTag;
 
 pub async fn list_of_posts(
-    Query(params): Query<PostsParams>,
+    caller: CurrentUser,
+    Query(params): Query<PageParams>,
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListOfPostsAnswer>> {
+) -> ApiResult<Json<PagedResponse<Sparse<MiniPost>>>> {
+    caller.require(state.config.privileges.posts_list)?;
     debug!("Post listing params: {params:?}");
-    // let fields_mas = get_fields_from_string(params.fields.clone());
-    // debug!("{:?}", &fields_mas);
-    // oki
-
-    let total = state.db.get_posts_count().await?;
-    let offset = {
-        match params.offset {
-            None => 0,
-            Some(i) => i,
-        }
-    };
+    let fields = Fields::parse(params.fields.as_deref());
+    let search = parse_post_query(params.query.as_deref().unwrap_or_default()).map_err(ApiError::Search)?;
+    if params.after.is_some() && search.sort != PostSort::Id {
+        return Err(ApiError::Search("Keyset pagination works only with sorting by id.".to_string()));
+    }
+    let page = params.page();
 
-    let (results_raw, _) = state.db.get_posts_in_page(offset, params.limit).await?;
-        // PostQuery::find_posts_in_page_with_filter(&state.db, offset, fields_mas, params.limit).await.unwrap();
+    let Page { total, results: results_raw } = state.db.search_posts(&search, caller.id(), page).await?;
     debug!("{results_raw:?}");
-    let mut results: Vec<MiniPost> = Vec::new();
+    let post_ids: Vec<i32> = results_raw.iter().map(|model| model.id).collect();
+    let mut tags: Vec<(i32, MicroTag)> = Vec::new();
+    if fields.has("tags") {
+        let (tag_post_ids, raw_tags): (Vec<i32>, Vec<_>) = state.db.get_post_tags(&post_ids).await?.into_iter().unzip();
+        tags = tag_post_ids.into_iter().zip(micro_tags(&state, &raw_tags).await?).collect();
+    }
+    let scores = if fields.has("score") { state.db.get_post_scores(&post_ids).await? } else { Default::default() };
+    let favorite_counts = if fields.has("favoriteCount") { state.db.get_post_favorite_counts(&post_ids).await? } else { Default::default() };
+    let comment_counts = if fields.has("commentCount") { state.db.get_post_comment_counts(&post_ids).await? } else { Default::default() };
+    let mut results: Vec<Sparse<MiniPost>> = Vec::new();
     for model in results_raw.iter() {
-        results.push(MiniPost::from_model(model, "data/avatarka.jpg".to_string(), 0, 0, 0, Vec::new()))
-    }   // TODO: заглушки :(
-
-    let posts = ListOfPostsAnswer {
-        query: params.fields,
-        offset,
-        limit: params.limit,
-        total,
-        results,
-    };
+        let thumbnail_url = get_post_thumbnail_path(model.id, get_post_security_hash(model.id, &state.config.secret));
+        let post_tags = tags.iter().filter(|(post_id, _)| *post_id == model.id).map(|(_, tag)| tag.clone()).collect();
+        let score = scores.get(&model.id).copied().unwrap_or_default();
+        let favorite_count = favorite_counts.get(&model.id).copied().unwrap_or_default();
+        let comment_count = comment_counts.get(&model.id).copied().unwrap_or_default();
+        results.push(fields.sparse(MiniPost::from_model(model, thumbnail_url, score, favorite_count, comment_count, post_tags)))
+    }
 
-    Ok(Json(posts))
+    Ok(Json(PagedResponse::new(params.query, page, total, results)))
     // end
 }
 
 pub async fn get_post_by_id(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    Query(params): Query<FieldsParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Sparse<PostAnswer>>> {
+    caller.require(state.config.privileges.posts_view)?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let raw_post = state.db.get_post_by_id(id).await?;
+    Ok(Json(fields.sparse(post_answer(&state, &caller, raw_post, &fields).await?)))
+}
+
+pub async fn update_post(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    JsonOrMultipart(params, mut files): JsonOrMultipart<UpdatePostQuery>, // Must be last extractor
+) -> ApiResult<Json<PostAnswer>> {
+    debug!("Trying to update post {id} with params: {params:?}");
+    let privileges = &state.config.privileges;
+    let raw_post = state.db.get_post_by_id(id).await?;
+    check_version(raw_post.version, params.version)?;
+
+    let mut form_data: post::ActiveModel = raw_post.clone().into();
+    if let Some(safety) = params.safety {
+        caller.require(privileges.posts_edit_safety)?;
+        validate_safety(&safety)?;
+        form_data.safety = Set(safety);
+    }
+    if let Some(source) = params.source {
+        caller.require(privileges.posts_edit_source)?;
+        validate_source(&source)?;
+        form_data.source = Set((!source.is_empty()).then_some(source));
+    }
+    if let Some(flags) = params.flags {
+        caller.require(privileges.posts_edit_flags)?;
+        form_data.flags = Set(validate_flags(&flags)?);
+    }
+    let mut related = PostRelated::default();
+    if let Some(names) = params.tags {
+        caller.require(privileges.posts_edit_tags)?;
+        related.tags = Some(plan_post_tags(&state, &caller, &names).await?);
+    }
+    if let Some(new_relations) = params.relations {
+        caller.require(privileges.posts_edit_relations)?;
+        related.relations = Some(validate_relations(&state, Some(raw_post.id), &new_relations).await?);
+    }
+    if let Some(new_notes) = params.notes {
+        caller.require(privileges.posts_edit_notes)?;
+        for note in new_notes.iter() {
+            validate_note(&note.polygon, &note.text).map_err(ApiError::InvalidPostNote)?;
+        }
+        related.notes = Some(new_notes.into_iter().map(|note| post_note::ActiveModel {
+            polygon: Set(serde_json::to_value(note.polygon).expect("Can't serialize note polygon")),
+            text: Set(note.text),
+            ..Default::default()
+        }).collect());
+    }
+    let mut uploads = Vec::new();
+    let mut content = None;
+    if params.content_token.is_some() || files.contains_key("content") {
+        caller.require(privileges.posts_edit_content)?;
+        let file = read_file(&state, &mut files, "content", params.content_token.as_deref())?
+            .ok_or_else(|| ApiError::MissingRequiredFile("Content is missing.".to_string()))?;
+        inspect_content(&state, &file.content, &file.content_type, Some(raw_post.id)).await?.apply(&mut form_data);
+        content = Some(file.content.clone());
+        uploads.push(file);
+    }
+    let mut thumbnail = None;
+    if params.thumbnail_token.is_some() || files.contains_key("thumbnail") {
+        caller.require(privileges.posts_edit_thumbnail)?;
+        let file = read_file(&state, &mut files, "thumbnail", params.thumbnail_token.as_deref())?
+            .ok_or_else(|| ApiError::MissingRequiredFile("Thumbnail is missing.".to_string()))?;
+        thumbnail = Some(prepare_thumbnail(&state, file.content.clone()).await?);
+        form_data.has_custom_thumbnail = Set(true);
+        uploads.push(file);
+    }
+
+    let old_mime_type = raw_post.mime_type;
+    let raw_post = state.db.update_post(id, params.version, form_data, related).await?;
+
+    // Files are only touched once the post is saved
+    let hash = get_post_security_hash(raw_post.id, &state.config.secret);
+    let thumbnail_path = get_post_thumbnail_path(raw_post.id, hash.clone());
+    if let Some(content) = content {
+        let old_path = get_post_content_path(raw_post.id, hash.clone(), &old_mime_type);
+        let new_path = get_post_content_path(raw_post.id, hash.clone(), &raw_post.mime_type);
+        fs::write(&new_path, content)?;
+        if old_path != new_path && FsPath::new(&old_path).exists() {
+            fs::remove_file(old_path)?;
+        }
+        // Thumbnail of old content is replaced, custom one is kept
+        if !raw_post.has_custom_thumbnail {
+            if FsPath::new(&thumbnail_path).exists() {
+                fs::remove_file(&thumbnail_path)?;
+            }
+            generate_thumbnail(&state, &raw_post).await;
+        }
+    }
+    if let Some(thumbnail) = thumbnail {
+        fs::write(&thumbnail_path, thumbnail)?;
+    }
+    for upload in uploads {
+        upload.release(&state)?;
+    }
+    info!("Post {id} updated");
+    Ok(Json(post_answer(&state, &caller, raw_post, &Fields::default()).await?))
+}
+
+pub async fn rate_post(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<RatePostQuery>, // Must be last extractor
+) -> ApiResult<Json<PostAnswer>> {
+    caller.require(state.config.privileges.posts_score)?;
+    if !(-1..=1).contains(&params.score) {
+        return Err(ApiError::InvalidScoreValue(format!("Score {} is invalid. Valid scores are -1, 0 and 1.", params.score)));
+    }
+    let user_id = caller.id().ok_or(AuthError::InsufficientPrivileges)?;
+    let raw_post = state.db.get_post_by_id(id).await?;
+    state.db.set_post_score(raw_post.id, user_id, params.score).await?;
+    debug!("User {user_id} rated post {id} with {}", params.score);
+    Ok(Json(post_answer(&state, &caller, raw_post, &Fields::default()).await?))
+}
+
+pub async fn favorite_post(
+    caller: CurrentUser,
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<PostAnswer>> {
+    caller.require(state.config.privileges.posts_favorite)?;
+    let user_id = caller.id().ok_or(AuthError::InsufficientPrivileges)?;
+    let raw_post = state.db.get_post_by_id(id).await?;
+    state.db.add_post_favorite(raw_post.id, user_id).await?;
+    Ok(Json(post_answer(&state, &caller, raw_post, &Fields::default()).await?))
+}
+
+pub async fn unfavorite_post(
+    caller: CurrentUser,
     Path(id): Path<u64>,
     State(state): State<Arc<AppState>>,
 ) -> ApiResult<Json<PostAnswer>> {
+    caller.require(state.config.privileges.posts_favorite)?;
+    let user_id = caller.id().ok_or(AuthError::InsufficientPrivileges)?;
     let raw_post = state.db.get_post_by_id(id).await?;
-    
+    state.db.remove_post_favorite(raw_post.id, user_id).await?;
+    Ok(Json(post_answer(&state, &caller, raw_post, &Fields::default()).await?))
+}
+
+/// Generates post thumbnail from its content, videos and flash are left without one.
+/// Failures are only logged, since post is still usable without thumbnail.
+async fn generate_thumbnail(state: &AppState, raw_post: &post::Model) {
+    if !matches!(raw_post.r#type.as_str(), "image" | "animation") {
+        return;
+    }
+    let hash = get_post_security_hash(raw_post.id, &state.config.secret);
+    let thumbnail_path = get_post_thumbnail_path(raw_post.id, hash.clone());
+    let content_path = get_post_content_path(raw_post.id, hash, &raw_post.mime_type);
+    let config = state.config.thumbnails.clone();
+    let result = tokio::task::spawn_blocking(move || generate_post_thumbnail(&config, &content_path, &thumbnail_path)).await;
+    match result {
+        Ok(Ok(())) => debug!("Thumbnail for post {} generated", raw_post.id),
+        Ok(Err(e)) => warn!("Can't generate thumbnail for post {}: {e}", raw_post.id),
+        Err(e) => warn!("Thumbnail task for post {} failed: {e}", raw_post.id),
+    }
+}
+
+/// Serves post thumbnail, generating it first when it is missing, e.g. for
+/// posts stored before thumbnails were generated on upload.
+pub async fn get_post_thumbnail(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<([(HeaderName, &'static str); 1], Vec<u8>)> {
+    let not_found = || ApiError::PostNotFound(format!("Thumbnail {name:?} not found."));
+    let (id, hash) = name.strip_suffix(".jpg").and_then(|stem| stem.split_once('_')).ok_or_else(not_found)?;
+    let id: u64 = id.parse().map_err(|_| not_found())?;
+    if hash != get_post_security_hash(id, &state.config.secret) {
+        return Err(not_found());
+    }
+    let thumbnail_path = get_post_thumbnail_path(id, hash.to_string());
+    if !FsPath::new(&thumbnail_path).exists() {
+        let raw_post = state.db.get_post_by_id(id).await?;
+        generate_thumbnail(&state, &raw_post).await;
+    }
+    let thumbnail = fs::read(&thumbnail_path).map_err(|_| not_found())?;
+    Ok(([(CONTENT_TYPE, "image/jpeg")], thumbnail))
+}
+
+pub fn micro_post(state: &AppState, id: i32) -> MicroPost {
+    MicroPost {
+        id,
+        thumbnail_url: get_post_thumbnail_path(id, get_post_security_hash(id, &state.config.secret)),
+    }
+}
+
+fn validate_safety(safety: &str) -> ApiResult<()> {
+    if !POST_SAFETIES.contains(&safety) {
+        return Err(ApiError::InvalidPostSafety(format!("Safety can be either of {POST_SAFETIES:?}.")));
+    }
+    Ok(())
+}
+
+fn validate_source(source: &str) -> ApiResult<()> {
+    if source.len() > 2048 {
+        return Err(ApiError::InvalidPostSource("Source is too long.".to_string()));
+    }
+    Ok(())
+}
+
+/// Returns flags as stored in database, comma separated or NULL when empty.
+fn validate_flags(flags: &[String]) -> ApiResult<Option<String>> {
+    if let Some(flag) = flags.iter().find(|flag| !POST_FLAGS.contains(&flag.as_str())) {
+        return Err(ApiError::InvalidPostFlag(format!("Flag {flag:?} is invalid, can be either of {POST_FLAGS:?}.")));
+    }
+    Ok((!flags.is_empty()).then(|| flags.join(",")))
+}
+
+/// Properties of post content, read when it is uploaded or replaced.
+struct ContentInfo {
+    checksum: String,
+    checksum_md5: String,
+    mime_type: String,
+    post_type: &'static str,
+    dimensions: Option<(u32, u32)>,
+    size: usize,
+}
+
+impl ContentInfo {
+    fn apply(self, form_data: &mut post::ActiveModel) {
+        form_data.r#type = Set(self.post_type.to_string());
+        form_data.checksum = Set(self.checksum);
+        form_data.checksum_md5 = Set(Some(self.checksum_md5));
+        form_data.file_size = Set(Some(self.size as i64));
+        form_data.image_width = Set(self.dimensions.map(|(width, _)| width as i32));
+        form_data.image_height = Set(self.dimensions.map(|(_, height)| height as i32));
+        form_data.mime_type = Set(self.mime_type);
+    }
+}
+
+/// Validates post content. `content_type` is used when type can't be guessed
+/// from content itself, files already uploaded as posts other than `post_id` are rejected.
+async fn inspect_content(state: &AppState, content: &[u8], content_type: &str, post_id: Option<i32>) -> ApiResult<ContentInfo> {
+    let checksum = get_content_checksum(content);
+    if let Some(existing) = state.db.get_post_by_checksum(&checksum).await? {
+        if Some(existing.id) != post_id {
+            return Err(ApiError::PostAlreadyUploaded(format!("Post already uploaded as {}.", existing.id)));
+        }
+    }
+    let mime_type = image::guess_format(content)
+        .map(|format| format.to_mime_type().to_string())
+        .unwrap_or(content_type.to_string());
+    if mime_guess2::get_mime_extensions_str(&mime_type).is_none() {
+        return Err(ApiError::InvalidPostContent(format!("Unhandled file type: {mime_type:?}.")));
+    }
+    let max_size = state.config.limits.max_upload_size_for(&mime_type);
+    if content.len() > max_size {
+        return Err(ApiError::Validation(format!("Files of type {mime_type:?} can't be larger than {max_size} bytes.")));
+    }
+    let dimensions = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+    let post_type = get_post_type(&mime_type);
+    if dimensions.is_none() && post_type == "image" && !state.config.allow_broken_uploads {
+        return Err(ApiError::InvalidPostContent("Unable to process image metadata.".to_string()));
+    }
+    Ok(ContentInfo {
+        checksum,
+        checksum_md5: get_content_md5(content),
+        mime_type,
+        post_type,
+        dimensions,
+        size: content.len(),
+    })
+}
+
+/// Encodes custom image as post thumbnail, it's written once the post is saved.
+async fn prepare_thumbnail(state: &AppState, content: Vec<u8>) -> ApiResult<Vec<u8>> {
+    let config = state.config.thumbnails.clone();
+    tokio::task::spawn_blocking(move || encode_custom_post_thumbnail(&config, &content))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+        .map_err(|e| ApiError::InvalidPostContent(format!("Can't process thumbnail: {e}.")))
+}
+
+/// Rejects relations to the post itself and to missing posts,
+/// repeated ids are dropped.
+async fn validate_relations(state: &AppState, post_id: Option<i32>, relations: &[u64]) -> ApiResult<Vec<i32>> {
+    let mut related_ids: Vec<i32> = Vec::new();
+    for id in relations.iter() {
+        let id = i32::try_from(*id).map_err(|_| ApiError::InvalidPostRelation(format!("Related post {id} not found.")))?;
+        if Some(id) == post_id {
+            return Err(ApiError::InvalidPostRelation("Post cannot relate to itself.".to_string()));
+        }
+        if !related_ids.contains(&id) {
+            related_ids.push(id);
+        }
+    }
+    let existing: Vec<i32> = state.db.get_posts_by_ids(&related_ids).await?.into_iter().map(|post| post.id).collect();
+    if let Some(id) = related_ids.iter().find(|id| !existing.contains(id)) {
+        return Err(ApiError::InvalidPostRelation(format!("Related post {id} not found.")));
+    }
+    Ok(related_ids)
+}
+
+/// Builds post representation from database model,
+/// fields which are not requested are left empty.
+async fn post_answer(state: &AppState, caller: &CurrentUser, raw_post: post::Model, fields: &Fields) -> ApiResult<PostAnswer> {
     let mut flags: Vec<String> = Vec::new();
-    if raw_post.flags.is_some() {
-        for part in raw_post.flags.unwrap().split(",") {
+    if let Some(raw_flags) = raw_post.flags {
+        for part in raw_flags.split(',') {
             flags.push(part.to_string());
         }
     }
 
-    let post = PostAnswer {
+    let mut tags = Vec::new();
+    if fields.has("tags") || fields.has("tagCount") {
+        let raw_tags: Vec<_> = state.db.get_post_tags(&[raw_post.id]).await?.into_iter().map(|(_, tag)| tag).collect();
+        tags = micro_tags(state, &raw_tags).await?;
+    }
+
+    let user = match raw_post.user_id {
+        Some(user_id) if fields.has("user") => {
+            let raw_user = state.db.get_user_by_id(user_id as u64).await?;
+            Some(User { avatar_url: get_avatar_url(&state.config.thumbnails, &raw_user), name: raw_user.name })
+        }
+        _ => None,
+    };
+
+    let mut score = 0;
+    if fields.has("score") {
+        score = state.db.get_post_scores(&[raw_post.id]).await?.get(&raw_post.id).copied().unwrap_or_default();
+    }
+    let own_score = match caller.id() {
+        Some(user_id) if fields.has("ownScore") => state.db.get_own_post_score(raw_post.id, user_id).await?,
+        _ => 0,
+    };
+
+    let mut favorited_by = Vec::new();
+    if fields.has("favoritedBy") || fields.has("favoriteCount") || fields.has("ownFavorite") {
+        favorited_by = state.db.get_post_favorited_by(raw_post.id).await?;
+    }
+    let own_favorite = caller.id().is_some_and(|user_id| favorited_by.iter().any(|raw_user| raw_user.id == user_id));
+
+    let mut comments = Vec::new();
+    if fields.has("comments") || fields.has("commentCount") {
+        let raw_comments = state.db.get_post_comments(raw_post.id).await?;
+        comments = comment_answers(state, caller, raw_comments).await?;
+    }
+
+    let mut relations = Vec::new();
+    if fields.has("relations") || fields.has("relationCount") {
+        relations = state.db.get_post_relations(raw_post.id).await?
+            .into_iter()
+            .map(|related_id| micro_post(state, related_id))
+            .collect();
+    }
+
+    let mut notes = Vec::new();
+    if fields.has("notes") || fields.has("noteCount") {
+        notes = state.db.get_post_notes(raw_post.id).await?
+            .into_iter()
+            .map(|raw_note| PostNote {
+                polygon: serde_json::from_value(raw_note.polygon).unwrap_or_default(),
+                text: raw_note.text,
+            })
+            .collect();
+    }
+
+    let mut pools = Vec::new();
+    if fields.has("pools") {
+        let raw_pools = state.db.get_post_pools(raw_post.id).await?;
+        pools = micro_pools(state, &raw_pools).await?;
+    }
+
+    Ok(PostAnswer {
         id: raw_post.id,
         version: raw_post.version,
         creation_time: raw_post.creation_time,
@@ -74,42 +446,115 @@ pub async fn get_post_by_id(
         file_size: raw_post.file_size,
         canvas_width: raw_post.image_width,
         canvas_height: raw_post.image_height,
-        content_url: get_post_content_path(raw_post.id, get_post_security_hash(id, &state.config.secret), &raw_post.mime_type),
-        thumbnail_url: get_post_thumbnail_path(raw_post.id, get_post_security_hash(id, &state.config.secret)),
-        flags, // TODO: Дальше чисто заглушки
-        tags: Vec::new(),
-        relations: Vec::new(),
-        user: None, 
-        score: 0,
-        own_score: 0,
-        own_favorite: false,
-        tag_count: 0,
-        favorite_count: 0,
-        comment_count: 0,
-        note_count: 0,
-        relation_count: 0,
-        feature_count: 0,
+        content_url: get_post_content_path(raw_post.id, get_post_security_hash(raw_post.id, &state.config.secret), &raw_post.mime_type),
+        thumbnail_url: get_post_thumbnail_path(raw_post.id, get_post_security_hash(raw_post.id, &state.config.secret)),
+        flags,
+        tag_count: tags.len() as i64,
+        tags,
+        relation_count: relations.len() as i64,
+        relations,
+        user,
+        score,
+        own_score: own_score.into(),
+        own_favorite,
+        favorite_count: favorited_by.len() as i64,
+        comment_count: comments.len() as i64,
+        note_count: notes.len() as i64,
+        feature_count: 0,           // TODO: Featuring posts isn't supported yet
         last_feature_time: None,
-        favorited_by: Vec::new(),
-        has_custom_thumbnail: false,
-        notes: Vec::new(),
-        comments: Vec::new(),
-        pools: Vec::new(),
+        favorited_by: favorited_by
+            .into_iter()
+            .map(|raw_user| User { avatar_url: get_avatar_url(&state.config.thumbnails, &raw_user), name: raw_user.name })
+            .collect(),
+        has_custom_thumbnail: raw_post.has_custom_thumbnail,
+        notes,
+        comments,
+        pools,
+    })
+}
+
+pub async fn create_post(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreatePostQuery>, // Must be last extractor
+) -> ApiResult<Json<PostAnswer>> {
+    debug!("Trying to create new post with params: {params:?}");
+    let privileges = &state.config.privileges;
+    if params.anonymous {
+        caller.require(privileges.posts_create_anonymous)?;
+    } else {
+        caller.require(privileges.posts_create_identified)?;
+    }
+
+    // Validation
+    validate_safety(&params.safety)?;
+    let flags = validate_flags(&params.flags)?;
+    if let Some(source) = params.source.as_deref() {
+        validate_source(source)?;
+    }
+    let related = PostRelated {
+        tags: Some(plan_post_tags(&state, &caller, &params.tags).await?),
+        relations: Some(validate_relations(&state, None, &params.relations).await?),
+        notes: None,
     };
 
-    Ok(Json(post))
+    // Content
+    let upload = state.uploads.lock().expect("Uploads mutex was poisoned!").get(&params.content_token)
+        .ok_or_else(|| ApiError::MissingRequiredFile(format!("Uploaded file {:?} not found.", params.content_token)))?;
+    let content = fs::read(upload.path())?;
+    let content_info = inspect_content(&state, &content, &upload.content_type, None).await?;
+    let mime_type = content_info.mime_type.clone();
+
+    let mut form_data = post::ActiveModel {
+        user_id: Set(if params.anonymous { None } else { caller.id() }),
+        safety: Set(params.safety),
+        source: Set(params.source),
+        version: Set(1),
+        flags: Set(flags),
+        ..Default::default()
+    };
+    content_info.apply(&mut form_data);
+    let id = state.db.create_post(form_data, related).await?.id;
+
+    // Moving file from temporary uploads to posts
+    let content_path = get_post_content_path(id, get_post_security_hash(id, &state.config.secret), &mime_type);
+    if let Err(e) = fs::rename(upload.path(), content_path) {
+        state.db.delete_post(id as u64).await?;
+        return Err(e.into());
+    }
+    state.uploads.lock().expect("Uploads mutex was poisoned!").get_and_remove(&params.content_token);
+    info!("Post {id} created");
+
+    let raw_post = state.db.get_post_by_id(id as u64).await?;
+    generate_thumbnail(&state, &raw_post).await;
+    Ok(Json(post_answer(&state, &caller, raw_post, &Fields::default()).await?))
 }
 
 pub async fn reverse_post_search(
+    caller: CurrentUser,
     State(state): State<Arc<AppState>>,
     Json(content_path):  Json<ReverseSearchQuery>
 ) -> ApiResult<Json<ReverseSearchAnswer>> {
+    caller.require(state.config.privileges.posts_reverse_search)?;
     // TODO: Пока что здесь просто заглушка
     if !state.uploads.lock().expect("Uploads mutex was poisoned!").is_existing(&content_path.content_token) {
-        return Err(ApiError::Uploads);
+        return Err(ApiError::MissingRequiredFile(format!("Uploaded file {:?} not found.", content_path.content_token)));
     };
     Ok(Json(ReverseSearchAnswer {
         exact_post: None,
         similar_posts: Vec::new(),
     }))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safeties_and_flags() {
+        assert!(validate_safety("sketchy").is_ok());
+        assert!(validate_safety("nsfw").is_err());
+        assert_eq!(validate_flags(&[]).unwrap(), None);
+        assert_eq!(validate_flags(&["loop".to_string(), "sound".to_string()]).unwrap(), Some("loop,sound".to_string()));
+        assert!(validate_flags(&["loop".to_string(), "glitch".to_string()]).is_err());
+    }
+}
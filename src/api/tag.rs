@@ -0,0 +1,391 @@
+use std::{collections::HashSet, sync::Arc};
+
+use axum::extract::{Json, Path, Query, State};
+use chrono::NaiveDateTime;
+use log::debug;
+use regex::Regex;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{fields::{Fields, FieldsParams, Sparse}, page::{PageParams, PagedResponse}},
+    db::{errors::{GetTagCategoryError, UpdateError}, repository::{PlannedTags, TagRelated}, schemas::{snapshot, tag}}, error::{check_version, ApiError, ApiResult}, AppState, CurrentUser
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MicroTag {
+    pub names: Vec<String>,
+    pub category: String,
+    pub usages: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagHttpAnswer {
+    pub version: i32,
+    pub names: Vec<String>,
+    pub category: String,
+    pub implications: Vec<MicroTag>,
+    pub suggestions: Vec<MicroTag>,
+    pub creation_time: NaiveDateTime,
+    pub last_edit_time: Option<NaiveDateTime>,
+    pub usages: i64,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagHttpQuery {
+    pub names: Vec<String>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub implications: Vec<String>,
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTagHttpQuery {
+    pub version: i32,
+    pub names: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub implications: Option<Vec<String>>,
+    pub suggestions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTagHttpQuery {
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTagsHttpQuery {
+    pub remove_version: i32,
+    pub remove: String,
+    pub merge_to_version: i32,
+    pub merge_to: String,
+    #[serde(default)]
+    pub merge_names: bool,
+    #[serde(default)]
+    pub merge_implications: bool,
+    #[serde(default)]
+    pub merge_suggestions: bool,
+}
+
+/// Builds short tag representations, keeping order of `tags`.
+pub async fn micro_tags(state: &AppState, tags: &[tag::Model]) -> ApiResult<Vec<MicroTag>> {
+    let ids: Vec<i32> = tags.iter().map(|tag| tag.id).collect();
+    let mut names = state.db.get_tag_names(&ids).await?;
+    let usages = state.db.get_tag_usages(&ids).await?;
+    Ok(tags.iter().map(|tag| MicroTag {
+        names: names.remove(&tag.id).unwrap_or_default(),
+        category: tag.category.clone(),
+        usages: usages.get(&tag.id).copied().unwrap_or_default(),
+    }).collect())
+}
+
+/// Short representations of children from `(parent_id, child_id)` pairs.
+async fn related_micro_tags(state: &AppState, relations: &[(i32, i32)]) -> ApiResult<Vec<MicroTag>> {
+    let child_ids: Vec<i32> = relations.iter().map(|(_, child_id)| *child_id).collect();
+    let mut children = state.db.get_tags_by_ids(&child_ids).await?;
+    children.sort_by_key(|tag| child_ids.iter().position(|id| *id == tag.id));
+    micro_tags(state, &children).await
+}
+
+/// Fields which are not requested are left empty.
+async fn tag_answer(state: &AppState, tag: tag::Model, fields: &Fields) -> ApiResult<TagHttpAnswer> {
+    let micro = micro_tags(state, std::slice::from_ref(&tag)).await?.remove(0);
+    let mut implications = Vec::new();
+    if fields.has("implications") {
+        let pairs = state.db.get_tag_implications(&[tag.id]).await?;
+        implications = related_micro_tags(state, &pairs).await?;
+    }
+    let mut suggestions = Vec::new();
+    if fields.has("suggestions") {
+        let pairs = state.db.get_tag_suggestions(&[tag.id]).await?;
+        suggestions = related_micro_tags(state, &pairs).await?;
+    }
+    Ok(TagHttpAnswer {
+        version: tag.version,
+        names: micro.names,
+        category: tag.category,
+        implications,
+        suggestions,
+        creation_time: tag.creation_time,
+        last_edit_time: tag.last_edit_time,
+        usages: micro.usages,
+        description: tag.description,
+    })
+}
+
+/// Checks names against `tag_name_regex` and drops case-insensitive duplicates.
+pub fn validate_tag_names(state: &AppState, names: &[String]) -> ApiResult<Vec<String>> {
+    let regex = Regex::new(&state.config.tag_name_regex).expect("Invalid tag_name_regex in config!");
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        if !regex.is_match(name) {
+            return Err(ApiError::InvalidTagName(format!("Tag name {name:?} must satisfy regex {:?}.", state.config.tag_name_regex)));
+        }
+        if seen.insert(name.to_lowercase()) {
+            result.push(name.to_string());
+        }
+    }
+    Ok(result)
+}
+
+/// Returns canonical name of existing category.
+async fn validate_tag_category(state: &AppState, category: &str) -> ApiResult<String> {
+    match state.db.get_tag_category_by_name(category).await {
+        Ok(category) => Ok(category.name),
+        Err(GetTagCategoryError::TagCategoryNotFound { .. }) => Err(ApiError::InvalidTagCategory(format!("Category {category:?} is invalid."))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Rejects names already taken by tags other than `tag_id`.
+async fn check_names_available(state: &AppState, names: &[String], tag_id: Option<i32>) -> ApiResult<()> {
+    let taken = state.db.get_tags_by_names(names).await?;
+    if let Some((name, _)) = taken.iter().find(|(_, tag)| Some(tag.id) != tag_id) {
+        return Err(ApiError::TagAlreadyExists(format!("Tag {:?} already exists.", name.name)));
+    }
+    Ok(())
+}
+
+/// Names taken by concurrent request are only caught by unique index of tag names.
+fn name_conflict(error: ApiError) -> ApiError {
+    match &error {
+        ApiError::Database(e) | ApiError::Update(UpdateError::DatabaseError(e)) if e.is_unique_violation() => {
+            ApiError::TagAlreadyExists("Tag with one of these names already exists.".to_string())
+        }
+        _ => error,
+    }
+}
+
+/// Rejects implications and suggestions pointing to the tag itself.
+fn check_no_self_relation(names: &[String], related: &[String]) -> ApiResult<()> {
+    if related.iter().any(|name| names.iter().any(|own| own.to_lowercase() == name.to_lowercase())) {
+        return Err(ApiError::InvalidTagRelation("Tag cannot imply or suggest itself.".to_string()));
+    }
+    Ok(())
+}
+
+/// Finds tags by their names. Missing tags are only planned, they get
+/// created in default category along with whatever refers to them.
+async fn plan_tags(state: &AppState, caller: &CurrentUser, names: &[String]) -> ApiResult<PlannedTags> {
+    let names = validate_tag_names(state, names)?;
+    let existing = state.db.get_tags_by_names(&names).await?;
+    let mut tag_ids: Vec<i32> = Vec::new();
+    let mut new_names: Vec<String> = Vec::new();
+    for name in names.into_iter() {
+        match existing.iter().find(|(tag_name, _)| tag_name.name.to_lowercase() == name.to_lowercase()) {
+            Some((_, tag)) => {
+                if !tag_ids.contains(&tag.id) {
+                    tag_ids.push(tag.id)
+                }
+            }
+            None => new_names.push(name),
+        }
+    }
+    let mut category = String::new();
+    if !new_names.is_empty() {
+        caller.require(state.config.privileges.tags_create)?;
+        category = state.db.get_default_tag_category().await?.name;
+    }
+    Ok(PlannedTags { tag_ids, new_names, category })
+}
+
+/// Adds every tag implied by `tag_ids`, following implications recursively.
+async fn add_implied_tags(state: &AppState, mut tag_ids: Vec<i32>) -> ApiResult<Vec<i32>> {
+    let mut known: HashSet<i32> = tag_ids.iter().copied().collect();
+    let mut frontier: Vec<i32> = tag_ids.clone();
+    while !frontier.is_empty() {
+        // Already known tags are skipped, so cycles end here
+        let implied: Vec<i32> = state.db.get_tag_implications(&frontier).await?
+            .into_iter()
+            .map(|(_, child_id)| child_id)
+            .filter(|child_id| known.insert(*child_id))
+            .collect();
+        tag_ids.extend(implied.iter().copied());
+        frontier = implied;
+    }
+    Ok(tag_ids)
+}
+
+/// Plans tags for a post by their names, adding implied ones.
+pub async fn plan_post_tags(state: &AppState, caller: &CurrentUser, names: &[String]) -> ApiResult<PlannedTags> {
+    let mut tags = plan_tags(state, caller, names).await?;
+    tags.tag_ids = add_implied_tags(state, tags.tag_ids).await?;
+    Ok(tags)
+}
+
+pub async fn list_tags(
+    caller: CurrentUser,
+    Query(params): Query<PageParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<PagedResponse<Sparse<TagHttpAnswer>>>> {
+    caller.require(state.config.privileges.tags_list)?;
+    // Only name filtering is supported, `*` works as wildcard
+    let name_pattern = params.query
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .find(|token| !token.contains(':'));
+    let page = params.page();
+
+    let raw_tags = state.db.get_tags_in_page(name_pattern, page).await?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let mut results = Vec::new();
+    for tag in raw_tags.results {
+        results.push(fields.sparse(tag_answer(&state, tag, &fields).await?));
+    }
+    Ok(Json(PagedResponse::new(params.query, page, raw_tags.total, results)))
+}
+
+pub async fn get_tag(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    Query(params): Query<FieldsParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Sparse<TagHttpAnswer>>> {
+    caller.require(state.config.privileges.tags_view)?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let tag = state.db.get_tag_by_name(&name).await?;
+    Ok(Json(fields.sparse(tag_answer(&state, tag, &fields).await?)))
+}
+
+pub async fn create_tag(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreateTagHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<TagHttpAnswer>> {
+    debug!("Trying to create new tag with params: {params:?}");
+    caller.require(state.config.privileges.tags_create)?;
+    let names = validate_tag_names(&state, &params.names)?;
+    if names.is_empty() {
+        return Err(ApiError::InvalidTagName("At least one name must be specified.".to_string()));
+    }
+    check_names_available(&state, &names, None).await?;
+    check_no_self_relation(&names, &params.implications)?;
+    check_no_self_relation(&names, &params.suggestions)?;
+    let category = match params.category {
+        Some(category) => validate_tag_category(&state, &category).await?,
+        None => state.db.get_default_tag_category().await?.name,
+    };
+
+    let related = TagRelated {
+        implications: Some(plan_tags(&state, &caller, &params.implications).await?),
+        suggestions: Some(plan_tags(&state, &caller, &params.suggestions).await?),
+    };
+
+    let form_data = tag::ActiveModel {
+        category: Set(category),
+        description: Set(params.description),
+        ..Default::default()
+    };
+    let tag = state.db.create_tag(&names, form_data, related).await.map_err(|e| name_conflict(e.into()))?;
+    Ok(Json(tag_answer(&state, tag, &Fields::default()).await?))
+}
+
+pub async fn update_tag(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdateTagHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<TagHttpAnswer>> {
+    debug!("Trying to update tag {name} with params: {params:?}");
+    let privileges = &state.config.privileges;
+    let tag = state.db.get_tag_by_name(&name).await?;
+    check_version(tag.version, params.version)?;
+
+    let mut names = None;
+    if let Some(new_names) = params.names {
+        caller.require(privileges.tags_edit_names)?;
+        let new_names = validate_tag_names(&state, &new_names)?;
+        if new_names.is_empty() {
+            return Err(ApiError::InvalidTagName("At least one name must be specified.".to_string()));
+        }
+        check_names_available(&state, &new_names, Some(tag.id)).await?;
+        names = Some(new_names);
+    }
+    let mut form_data: tag::ActiveModel = tag.clone().into();
+    if let Some(category) = params.category {
+        caller.require(privileges.tags_edit_category)?;
+        form_data.category = Set(validate_tag_category(&state, &category).await?);
+    }
+    if let Some(description) = params.description {
+        caller.require(privileges.tags_edit_description)?;
+        form_data.description = Set(Some(description));
+    }
+    let own_names = match &names {
+        Some(names) => names.clone(),
+        None => state.db.get_tag_names(&[tag.id]).await?.remove(&tag.id).unwrap_or_default(),
+    };
+    let mut related = TagRelated::default();
+    if let Some(new_implications) = params.implications {
+        caller.require(privileges.tags_edit_implications)?;
+        check_no_self_relation(&own_names, &new_implications)?;
+        related.implications = Some(plan_tags(&state, &caller, &new_implications).await?);
+    }
+    if let Some(new_suggestions) = params.suggestions {
+        caller.require(privileges.tags_edit_suggestions)?;
+        check_no_self_relation(&own_names, &new_suggestions)?;
+        related.suggestions = Some(plan_tags(&state, &caller, &new_suggestions).await?);
+    }
+
+    let tag = state.db.update_tag(tag.id as u64, params.version, form_data, names.as_deref(), related).await.map_err(|e| name_conflict(e.into()))?;
+    Ok(Json(tag_answer(&state, tag, &Fields::default()).await?))
+}
+
+pub async fn delete_tag(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeleteTagHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    caller.require(state.config.privileges.tags_delete)?;
+    let tag = state.db.get_tag_by_name(&name).await?;
+    check_version(tag.version, params.version)?;
+    state.db.delete_tag(tag.id as u64).await?;
+    debug!("Tag {name} deleted!");
+    Ok("{}")
+}
+
+pub async fn merge_tags(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<MergeTagsHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<TagHttpAnswer>> {
+    debug!("Trying to merge tags with params: {params:?}");
+    caller.require(state.config.privileges.tags_merge)?;
+    let source = state.db.get_tag_by_name(&params.remove).await?;
+    let target = state.db.get_tag_by_name(&params.merge_to).await?;
+    check_version(source.version, params.remove_version)?;
+    check_version(target.version, params.merge_to_version)?;
+    if source.id == target.id {
+        return Err(ApiError::InvalidTagRelation("Cannot merge tag with itself.".to_string()));
+    }
+
+    let source_name = state.db.get_tag_names(&[source.id]).await?
+        .remove(&source.id)
+        .and_then(|names| names.into_iter().next())
+        .unwrap_or(params.remove);
+    let target_name = state.db.get_tag_names(&[target.id]).await?
+        .remove(&target.id)
+        .and_then(|names| names.into_iter().next())
+        .unwrap_or(params.merge_to);
+    let snapshot = snapshot::ActiveModel {
+        resource_type: Set("tag".to_string()),
+        operation: Set("merged".to_string()),
+        user_id: Set(caller.id()),
+        data: Set(Some(serde_json::to_vec(&("tag", &target_name)).expect("Can't serialize snapshot"))),
+        resource_name: Set(source_name),
+        resource_pkey: Set(source.id),
+        ..Default::default()
+    };
+    let tag = state.db.merge_tags(source.id, target.id, params.merge_names, params.merge_implications, params.merge_suggestions, snapshot).await?;
+    debug!("Tag {} merged into {target_name}!", source.id);
+    Ok(Json(tag_answer(&state, tag, &Fields::default()).await?))
+}
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::extract::{Json, Path, State};
+use log::debug;
+use sea_orm::Set;
+use serde::Deserialize;
+
+use crate::{
+    api::category::{category_answer, check_deletable, list_answer, validate_color, validate_name, CategoryHttpAnswer, DeleteCategoryHttpQuery, ListCategoriesHttpResponse},
+    db::{errors::GetTagCategoryError, schemas::tag_category}, error::{check_version, ApiError, ApiResult}, AppState, CurrentUser
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagCategoryHttpQuery {
+    pub name: String,
+    pub color: String,
+    pub order: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTagCategoryHttpQuery {
+    pub version: i32,
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub order: Option<i32>,
+}
+
+/// Rejects name taken by category other than `id`.
+async fn check_name_available(state: &AppState, name: &str, id: Option<i32>) -> ApiResult<()> {
+    match state.db.get_tag_category_by_name(name).await {
+        Ok(existing) if Some(existing.id) != id => Err(ApiError::TagCategoryAlreadyExists(format!("Tag category {name:?} already exists."))),
+        Ok(_) | Err(GetTagCategoryError::TagCategoryNotFound { .. }) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn list_tag_categories(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<ListCategoriesHttpResponse>> {
+    caller.require(state.config.privileges.tag_categories_list)?;
+    let usages = state.db.get_tag_category_usages().await?;
+    Ok(Json(list_answer(state.db.get_tag_categories().await?, &usages)))
+}
+
+pub async fn get_tag_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    caller.require(state.config.privileges.tag_categories_view)?;
+    let raw = state.db.get_tag_category_by_name(&name).await?;
+    Ok(Json(category_answer(raw, &state.db.get_tag_category_usages().await?)))
+}
+
+pub async fn create_tag_category(
+    caller: CurrentUser,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<CreateTagCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    debug!("Trying to create new tag category with params: {params:?}");
+    caller.require(state.config.privileges.tag_categories_create)?;
+    validate_name(&state.config.tag_category_name_regex, &params.name).map_err(ApiError::InvalidTagCategoryName)?;
+    validate_color(&params.color).map_err(ApiError::InvalidTagCategoryColor)?;
+    check_name_available(&state, &params.name, None).await?;
+    let form_data = tag_category::ActiveModel {
+        name: Set(params.name),
+        color: Set(params.color),
+        order: Set(params.order),
+        ..Default::default()
+    };
+    let raw = state.db.create_tag_category(form_data).await?;
+    Ok(Json(category_answer(raw, &HashMap::new())))
+}
+
+pub async fn update_tag_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdateTagCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    debug!("Trying to update tag category {name} with params: {params:?}");
+    let privileges = &state.config.privileges;
+    let raw = state.db.get_tag_category_by_name(&name).await?;
+    check_version(raw.version, params.version)?;
+
+    let mut form_data: tag_category::ActiveModel = raw.clone().into();
+    if let Some(new_name) = params.name {
+        caller.require(privileges.tag_categories_edit_name)?;
+        validate_name(&state.config.tag_category_name_regex, &new_name).map_err(ApiError::InvalidTagCategoryName)?;
+        check_name_available(&state, &new_name, Some(raw.id)).await?;
+        form_data.name = Set(new_name);
+    }
+    if let Some(color) = params.color {
+        caller.require(privileges.tag_categories_edit_color)?;
+        validate_color(&color).map_err(ApiError::InvalidTagCategoryColor)?;
+        form_data.color = Set(color);
+    }
+    if let Some(order) = params.order {
+        caller.require(privileges.tag_categories_edit_order)?;
+        form_data.order = Set(order);
+    }
+
+    let raw = state.db.update_tag_category(raw.id as u64, params.version, form_data).await?;
+    Ok(Json(category_answer(raw, &state.db.get_tag_category_usages().await?)))
+}
+
+pub async fn set_default_tag_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<CategoryHttpAnswer>> {
+    caller.require(state.config.privileges.tag_categories_set_default)?;
+    let raw = state.db.get_tag_category_by_name(&name).await?;
+    let raw = state.db.set_default_tag_category(raw.id as u64).await?;
+    Ok(Json(category_answer(raw, &state.db.get_tag_category_usages().await?)))
+}
+
+pub async fn delete_tag_category(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeleteCategoryHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    caller.require(state.config.privileges.tag_categories_delete)?;
+    let raw = state.db.get_tag_category_by_name(&name).await?;
+    check_version(raw.version, params.version)?;
+    let usages = state.db.get_tag_category_usages().await?;
+    check_deletable(&raw, &usages, "Tag category", "tags").map_err(ApiError::TagCategoryIsInUse)?;
+    state.db.delete_tag_category(raw.id as u64).await?;
+    debug!("Tag category {name} deleted!");
+    Ok("{}")
+}
@@ -15,16 +15,16 @@ pub async fn test(
     let body = Json(json!({"auth": format!("{auth:?}"), "uploads": format!("{:?}", state)}));
     
     debug!("{body:?}");
-    return Ok(body);
+    Ok(body)
 
 }
 
 pub async fn newtest(
 ) -> ApiResult<Json<Value>> {
-    return Err(ApiError::Test(TestError::ItsJustForTest));
+    Err(ApiError::Test(TestError::ItsJustForTest))
 }
 
 pub async fn newtest2(
 ) -> ApiResult<Json<Value>> {
-    return Err(ApiError::Test(TestError::SecondEntry));
+    Err(ApiError::Test(TestError::SecondEntry))
 }
\ No newline at end of file
@@ -1,14 +1,19 @@
-use std::str::FromStr;
+use std::{fs, path::Path as FsPath, str::FromStr};
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State, Json};
 use chrono::{Local, NaiveDateTime};
 use serde::{Serialize, Deserialize};
 use log::debug;
+use regex::Regex;
 use sea_orm::Set;
 
 use crate::{
-    db::schemas::user, error::ApiResult, AppState, AvatarStyle, UserRank
+    api::{data::{read_file, JsonOrMultipart, UploadedFile}, fields::{Fields, Sparse}, page::{PageParams, PagedResponse}},
+    db::{errors::GetUserError, schemas::user},
+    error::{check_version, ApiError, ApiResult, AuthError},
+    func::{auth::CURRENT_PASSWORD_REVISION, thumbnail::encode_avatar, user::{get_avatar_path, get_avatar_url}},
+    auth::hash_new_password, AppState, AvatarStyle, CurrentUser, UserRank
 };
 
 #[derive(Serialize, Deserialize)]
@@ -45,47 +50,237 @@ pub struct MicroUser {
     pub avatar_url: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UserHttpQuery {
-    #[serde(rename = "bump-login")]
-    pub bump_login: bool,
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserHttpQuery {
+    pub version: i32,
+    pub name: Option<String>,
+    pub password: Option<String>,
+    pub email: Option<String>,
+    pub rank: Option<UserRank>,
+    pub avatar_style: Option<AvatarStyle>,
+    pub avatar_token: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeleteUserHttpQuery {
+    pub version: i32,
+}
+
+/// Liked and disliked post counts are private, others see zeros.
+/// Email is shown only to the user and to those who can edit any email,
+/// counts which are not requested are left zero.
+async fn user_answer(state: &AppState, caller: &CurrentUser, raw_user: user::Model, fields: &Fields) -> ApiResult<UserHttpAnswer> {
+    let email_visible = caller.is(&raw_user.name) || caller.require(state.config.privileges.users_edit_any_email).is_ok();
+    let (liked_post_count, disliked_post_count) = if caller.is(&raw_user.name) && (fields.has("likedPostCount") || fields.has("dislikedPostCount")) {
+        state.db.get_user_score_counts(raw_user.id).await?
+    } else {
+        (0, 0)
+    };
+    let favorite_post_count = if fields.has("favoritePostCount") { state.db.get_user_favorite_count(raw_user.id).await? } else { 0 };
+    let comment_count = if fields.has("commentCount") { state.db.get_user_comment_count(raw_user.id).await? } else { 0 };
+    Ok(UserHttpAnswer {
+        avatar_url: get_avatar_url(&state.config.thumbnails, &raw_user),
+        name: raw_user.name,
+        creation_time: raw_user.creation_time,
+        last_login_time: raw_user.last_login_time,
+        version: raw_user.version,
+        rank: UserRank::from_str(&raw_user.rank).unwrap(),
+        avatar_style: AvatarStyle::from_str(&raw_user.avatar_style).unwrap(),
+        comment_count: comment_count as i32,
+        uploaded_post_count: 0,                         // TODO!
+        favorite_post_count: favorite_post_count as i32,
+        liked_post_count: liked_post_count as i32,
+        disliked_post_count: disliked_post_count as i32,
+        email: raw_user.email.filter(|_| email_visible),
+    })
+}
+
+fn validate_user_name(state: &AppState, name: &str) -> ApiResult<()> {
+    let regex = Regex::new(&state.config.user_name_regex).expect("Invalid user_name_regex in config!");
+    if !regex.is_match(name) {
+        return Err(ApiError::InvalidUserName(format!("User name {name:?} must satisfy regex {:?}.", state.config.user_name_regex)));
+    }
+    Ok(())
+}
+
+fn validate_password(state: &AppState, password: &str) -> ApiResult<()> {
+    let regex = Regex::new(&state.config.password_regex).expect("Invalid password_regex in config!");
+    if !regex.is_match(password) {
+        return Err(ApiError::InvalidPassword(format!("Password must satisfy regex {:?}.", state.config.password_regex)));
+    }
+    Ok(())
+}
+
+/// Ranks which can't be stored for registered users.
+fn validate_rank(rank: UserRank) -> ApiResult<()> {
+    if rank == UserRank::Anonymous || rank == UserRank::Nobody {
+        return Err(ApiError::InvalidRank(format!("Rank {rank} can't be given to user.")));
+    }
+    Ok(())
 }
 
-impl Default for UserHttpQuery {
-    fn default() -> Self {
-        Self { bump_login: false }
+async fn check_name_available(state: &AppState, name: &str) -> ApiResult<()> {
+    match state.db.get_user_by_name(name).await {
+        Ok(_) => Err(ApiError::UserAlreadyExists(format!("User {name:?} already exists."))),
+        Err(GetUserError::UserNotFound { .. }) => Ok(()),
+        Err(e) => Err(e.into()),
     }
 }
 
+/// Validates and resizes manual avatar of user `name`, encoded PNG is written
+/// by caller once user is saved. Without new content the already stored avatar
+/// is kept, if there is one, and nothing is returned.
+async fn prepare_avatar(state: &AppState, name: &str, file: Option<&UploadedFile>) -> ApiResult<Option<Vec<u8>>> {
+    let Some(file) = file else {
+        if FsPath::new(&get_avatar_path(name)).exists() {
+            return Ok(None);
+        }
+        return Err(ApiError::InvalidAvatar("Avatar content missing.".to_string()));
+    };
+    let (config, content) = (state.config.thumbnails.clone(), file.content.clone());
+    tokio::task::spawn_blocking(move || encode_avatar(&config, &content))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+        .map(Some)
+        .map_err(|e| ApiError::InvalidAvatar(format!("Can't process avatar: {e}.")))
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct UserHttpQuery {
+    #[serde(rename = "bump-login", default)]
+    pub bump_login: bool,
+    pub fields: Option<String>,
+}
+
 pub async fn get_user(
+    caller: CurrentUser,
     Path(user): Path<String>,
     params: Option<Query<UserHttpQuery>>,
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<UserHttpAnswer>> {
+) -> ApiResult<Json<Sparse<UserHttpAnswer>>> {
+    if !caller.is(&user) {
+        caller.require(state.config.privileges.users_view)?;
+    }
 
     let mut raw_user = state.db.get_user_by_name(&user).await?;
 
     // Update last login time if needed P.S. Лишние операции... так то это всё true false нахуй не надо!
     let Query(params) = params.unwrap_or_default();
-    if params.bump_login == true {
+    if params.bump_login {
         raw_user = state.db.update_last_login_time(&raw_user.name).await?
     }
 
-    Ok(Json(UserHttpAnswer {
-        name: user,
-        creation_time: raw_user.creation_time,
-        last_login_time: raw_user.last_login_time,
-        version: raw_user.version,
-        rank: UserRank::from_str(&raw_user.rank).unwrap(),
-        avatar_style: AvatarStyle::from_str(&raw_user.avatar_style).unwrap(),
-        avatar_url: "data/avatarka.jpg".to_string(),    // TODO! Hardcoded shit!
-        comment_count: 0,                               // TODO!
-        uploaded_post_count: 0,                         // TODO!
-        favorite_post_count: 0,                         // TODO!
-        liked_post_count: 0,                            // TODO!
-        disliked_post_count: 0,                         // TODO!
-        email: raw_user.email,
-    }))
+    let fields = Fields::parse(params.fields.as_deref());
+    Ok(Json(fields.sparse(user_answer(&state, &caller, raw_user, &fields).await?)))
+}
+
+pub async fn list_users(
+    caller: CurrentUser,
+    Query(params): Query<PageParams>,
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<PagedResponse<Sparse<UserHttpAnswer>>>> {
+    caller.require(state.config.privileges.users_list)?;
+    // Only name filtering is supported, `*` works as wildcard
+    let name_pattern = params.query
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|token| token.strip_prefix("name:").unwrap_or(token))
+        .find(|token| !token.contains(':'));
+    let page = params.page();
+    let raw_users = state.db.get_users_in_page(name_pattern, page).await?;
+    let fields = Fields::parse(params.fields.as_deref());
+    let mut results = Vec::new();
+    for raw_user in raw_users.results {
+        results.push(fields.sparse(user_answer(&state, &caller, raw_user, &fields).await?));
+    }
+    Ok(Json(PagedResponse::new(params.query, page, raw_users.total, results)))
+}
+
+pub async fn update_user(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    JsonOrMultipart(params, mut files): JsonOrMultipart<UpdateUserHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<UserHttpAnswer>> {
+    debug!("Trying to update user {name}");
+    let privileges = &state.config.privileges;
+    let raw_user = state.db.get_user_by_name(&name).await?;
+    check_version(raw_user.version, params.version)?;
+
+    let mut form_data: user::ActiveModel = raw_user.clone().into();
+    if let Some(new_name) = params.name {
+        caller.require_for(&name, privileges.users_edit_self_name, privileges.users_edit_any_name)?;
+        validate_user_name(&state, &new_name)?;
+        if new_name != raw_user.name {
+            check_name_available(&state, &new_name).await?;
+        }
+        form_data.name = Set(new_name);
+    }
+    if let Some(password) = params.password {
+        caller.require_for(&name, privileges.users_edit_self_pass, privileges.users_edit_any_pass)?;
+        validate_password(&state, &password)?;
+        let (password_hash, password_salt) = hash_new_password(&state, &password).await?;
+        form_data.password_hash = Set(password_hash);
+        form_data.password_salt = Set(Some(password_salt));
+        form_data.password_revision = Set(CURRENT_PASSWORD_REVISION);
+    }
+    if let Some(email) = params.email {
+        caller.require_for(&name, privileges.users_edit_self_email, privileges.users_edit_any_email)?;
+        form_data.email = Set(Some(email).filter(|email| !email.is_empty()));
+    }
+    if let Some(rank) = params.rank {
+        caller.require_for(&name, privileges.users_edit_self_rank, privileges.users_edit_any_rank)?;
+        validate_rank(rank)?;
+        if rank > caller.rank() {
+            return Err(AuthError::InsufficientPrivileges.into());
+        }
+        form_data.rank = Set(rank.to_string());
+    }
+    let (mut avatar_file, mut avatar) = (None, None);
+    if let Some(avatar_style) = params.avatar_style {
+        caller.require_for(&name, privileges.users_edit_self_avatar, privileges.users_edit_any_avatar)?;
+        if let AvatarStyle::Manual = avatar_style {
+            avatar_file = read_file(&state, &mut files, "avatar", params.avatar_token.as_deref())?;
+            avatar = prepare_avatar(&state, &raw_user.name, avatar_file.as_ref()).await?;
+        }
+        form_data.avatar_style = Set(avatar_style.to_string());
+    }
+
+    let old_avatar_path = get_avatar_path(&raw_user.name);
+    let raw_user = state.db.update_user(raw_user.id as u64, params.version, form_data).await?;
+    // Avatar follows renamed user
+    if raw_user.name != name && FsPath::new(&old_avatar_path).exists() {
+        fs::rename(old_avatar_path, get_avatar_path(&raw_user.name))?;
+    }
+    if let Some(avatar) = avatar {
+        fs::write(get_avatar_path(&raw_user.name), avatar)?;
+    }
+    if let Some(avatar_file) = avatar_file {
+        avatar_file.release(&state)?;
+    }
+    Ok(Json(user_answer(&state, &caller, raw_user, &Fields::default()).await?))
+}
+
+pub async fn delete_user(
+    caller: CurrentUser,
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<DeleteUserHttpQuery>, // Must be last extractor
+) -> ApiResult<&'static str> {
+    let privileges = &state.config.privileges;
+    caller.require_for(&name, privileges.users_delete_self, privileges.users_delete_any)?;
+    let raw_user = state.db.get_user_by_name(&name).await?;
+    check_version(raw_user.version, params.version)?;
+    state.db.delete_user(raw_user.id as u64).await?;
+    let avatar_path = get_avatar_path(&raw_user.name);
+    if FsPath::new(&avatar_path).exists() {
+        fs::remove_file(avatar_path)?;
+    }
+    debug!("User {name} deleted!");
+    Ok("{}")
 }
 
 #[derive(Deserialize, Debug)]
@@ -96,39 +291,61 @@ pub struct CreateUserHttpQuery {
     pub rank: Option<UserRank>,
     #[serde(rename = "avatarStyle")]
     pub avatar_style: Option<AvatarStyle>,
+    #[serde(rename = "avatarToken")]
+    pub avatar_token: Option<String>,
 }
 
 pub async fn create_user(
+    caller: CurrentUser,
     State(state): State<Arc<AppState>>,
-    Json(params): Json<CreateUserHttpQuery>, // ЭТА ЕБУЧАЯ ХУЙНЯ ДОЛЖНА БЫТЬ ПОСЛЕДНЕЙ, НАВОДИСЬ НА JSON И ПРОЧИТАЙ ПОСЛЕДНЮЮ СТРОКУ СПРАВКИ
+    JsonOrMultipart(params, mut files): JsonOrMultipart<CreateUserHttpQuery>, // ЭТА ЕБУЧАЯ ХУЙНЯ ДОЛЖНА БЫТЬ ПОСЛЕДНЕЙ, НАВОДИСЬ НА JSON И ПРОЧИТАЙ ПОСЛЕДНЮЮ СТРОКУ СПРАВКИ
 ) -> ApiResult<Json<UserHttpAnswer>> {
-    debug!("Trying to create new user with credentials: {params:?}");
+    debug!("Trying to create new user: {}", params.name);
+    match caller.0 {
+        None => caller.require(state.config.privileges.users_create_self)?,
+        Some(_) => caller.require(state.config.privileges.users_create_any)?,
+    }
+    validate_user_name(&state, &params.name)?;
+    validate_password(&state, &params.password)?;
+    check_name_available(&state, &params.name).await?;
+    let rank = match params.rank {
+        // First registered user has to be able to administer the rest
+        _ if state.db.get_users_count().await? == 0 => UserRank::Administrator,
+        Some(rank) => {
+            validate_rank(rank)?;
+            caller.require(state.config.privileges.users_create_any)?;
+            if caller.rank() <= rank {
+                return Err(AuthError::InsufficientPrivileges.into());
+            }
+            rank
+        }
+        None => state.config.default_rank,
+    };
+    let avatar_style = params.avatar_style.unwrap_or(AvatarStyle::Gravatar);
+    let (mut avatar_file, mut avatar) = (None, None);
+    if let AvatarStyle::Manual = avatar_style {
+        avatar_file = read_file(&state, &mut files, "avatar", params.avatar_token.as_deref())?;
+        avatar = prepare_avatar(&state, &params.name, avatar_file.as_ref()).await?;
+    }
+    let (password_hash, password_salt) = hash_new_password(&state, &params.password).await?;
     let form_data = user::ActiveModel {
-        name: Set(params.name.clone()),            // TODO!
-        password_hash: Set("aaa".to_string()),     // Переделать под Default
-        password_salt: Set(Some("aaa".to_string())),     // За пример взять UserToken
-        email: Set(params.email.clone()),
-        rank: Set(UserRank::Administrator.to_string()),
+        name: Set(params.name.clone()),
+        password_hash: Set(password_hash),
+        password_salt: Set(Some(password_salt)),
+        email: Set(params.email.clone().filter(|email| !email.is_empty())),
+        rank: Set(rank.to_string()),
         creation_time: Set(Local::now().naive_local().to_owned()),
-        avatar_style: Set(AvatarStyle::Gravatar.to_string()),
+        avatar_style: Set(avatar_style.to_string()),
         ..Default::default()
     };
     let created_user = state.db.create_user(form_data).await?;
+    if let Some(avatar) = avatar {
+        fs::write(get_avatar_path(&params.name), avatar)?;
+    }
+    if let Some(avatar_file) = avatar_file {
+        avatar_file.release(&state)?;
+    }
 
     let raw_user = state.db.get_user_by_id(created_user.id.unwrap() as u64).await?;
-    Ok(Json(UserHttpAnswer {
-        name: raw_user.name,
-        creation_time: raw_user.creation_time,
-        last_login_time: raw_user.last_login_time,
-        version: raw_user.version,
-        rank: UserRank::from_str(&raw_user.rank).unwrap(),
-        avatar_style: AvatarStyle::from_str(&raw_user.avatar_style).unwrap(),
-        avatar_url: "data/avatarka.jpg".to_string(),    // TODO! Hardcoded shit!
-        comment_count: 0,                               // TODO!
-        uploaded_post_count: 0,                         // TODO!
-        favorite_post_count: 0,                         // TODO!
-        liked_post_count: 0,                            // TODO!
-        disliked_post_count: 0,                         // TODO!
-        email: raw_user.email,
-    }))
+    Ok(Json(user_answer(&state, &caller, raw_user, &Fields::default()).await?))
 }
\ No newline at end of file
@@ -1,14 +1,15 @@
 use std::sync::Arc;
 
-use axum::{extract::{Path, State}, Json};
-use chrono::{DateTime, Local, Months, NaiveDateTime};
+use axum::{extract::{Path, Query, State}, Json};
+use chrono::{DateTime, Duration, Local, NaiveDateTime};
 use sea_orm::Set;
 use serde::{Deserialize, Serialize};
 use log::debug;
 use uuid::Uuid;
 
 use crate::{
-    db::schemas::user_token, error::{ApiError, ApiResult}, AppState
+    api::{fields::{Fields, Sparse}, page::{PageParams, PagedResponse}},
+    db::schemas::user_token, error::{check_version, ApiError, ApiResult}, func::user::get_avatar_url, AppState, CurrentUser
 };
 
 use super::user::MicroUser;
@@ -35,59 +36,123 @@ pub struct CreateUserTokenHttpQuery {
     pub expiration_time: Option<DateTime<Local>>,
 }
 
-// Внимание! Строчки дальше отвечают за состояния по умолчанию при создании токена
-// проблема в том что при логине на фронтенде "expiration_time" не указывается
-// и будет браться от сюда. TODO: Брать значение offset'a из конфига, а не хардкод!
-impl Default for CreateUserTokenHttpQuery {
-    fn default() -> Self {
-        Self {
-            enabled: Some(true),
-            note: Some("Undefined due creation".to_string()),
-            expiration_time: Some(Local::now().checked_add_months(Months::new(12)).unwrap())
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserTokenHttpQuery {
+    pub version: i32,
+    pub enabled: Option<bool>,
+    pub note: Option<String>,
+    pub expiration_time: Option<DateTime<Local>>,
+}
+
+fn user_token_answer(user: MicroUser, raw_token: user_token::Model) -> UserTokenHttpResponse {
+    UserTokenHttpResponse {
+        user,
+        token: raw_token.token,
+        note: raw_token.note,
+        enabled: raw_token.enabled,
+        expiration_time: raw_token.expiration_time,
+        creation_time: raw_token.creation_time,
+        last_edit_time: raw_token.last_edit_time,
+        last_usage_time: raw_token.last_usage_time,
+        version: raw_token.version,
+    }
+}
+
+/// Expiration must be in the future and within `tokens.max_lifetime`.
+fn validate_expiration(state: &AppState, expiration_time: DateTime<Local>) -> ApiResult<()> {
+    let now = Local::now();
+    if expiration_time <= now {
+        return Err(ApiError::InvalidUserTokenExpiration("Expiration time must be in the future.".to_string()));
+    }
+    if let Some(max_lifetime) = state.config.tokens.max_lifetime {
+        if expiration_time > now + Duration::days(max_lifetime.into()) {
+            return Err(ApiError::InvalidUserTokenExpiration(format!("Token can't live longer than {max_lifetime} days.")));
         }
     }
+    Ok(())
 }
 
 pub async fn create_usertoken( // POST
+    caller: CurrentUser,
     Path(user): Path<String>,
     State(state): State<Arc<AppState>>,
     Json(params): Json<CreateUserTokenHttpQuery>, // ЭТА ЕБУЧАЯ ХУЙНЯ ДОЛЖНА БЫТЬ ПОСЛЕДНЕЙ, НАВОДИСЬ НА JSON И ПРОЧИТАЙ ПОСЛЕДНЮЮ СТРОКУ СПРАВКИ
 ) -> ApiResult<Json<UserTokenHttpResponse>> {
     debug!("Trying to create new user-token for '{user}' with params: {params:?}");
+    let privileges = &state.config.privileges;
+    caller.require_for(&user, privileges.user_tokens_create_self, privileges.user_tokens_create_any)?;
     let user = state.db.get_user_by_name(&user).await?;
+    if let Some(max_per_user) = state.config.tokens.max_per_user {
+        if state.db.get_active_user_tokens_count(user.id as u64).await? >= max_per_user {
+            return Err(ApiError::Validation(format!("User can't have more than {max_per_user} active tokens.")));
+        }
+    }
+    // Frontend doesn't send expiration time on login
+    let expiration_time = match params.expiration_time {
+        Some(expiration_time) => {
+            validate_expiration(&state, expiration_time)?;
+            expiration_time
+        }
+        None => Local::now() + Duration::days(state.config.tokens.default_lifetime.into()),
+    };
     let form_data = user_token::ActiveModel {
         user_id: Set(user.id),
         token: Set(Uuid::new_v4().to_string()),
-        // Ооо! Пресвятой колхоз!
-        note: Set(Some(params.note.unwrap_or(CreateUserTokenHttpQuery::default().note.unwrap()))),
-        enabled: Set(params.enabled.unwrap_or(CreateUserTokenHttpQuery::default().enabled.unwrap())),
-        // Ооо! Пресвятой колхоз со временем!
-        expiration_time: Set(Some(params.expiration_time.unwrap_or(CreateUserTokenHttpQuery::default().expiration_time.unwrap()).naive_utc())),
+        note: Set(params.note),
+        enabled: Set(params.enabled.unwrap_or(true)),
+        expiration_time: Set(Some(expiration_time.naive_utc())),
         ..Default::default()
     };
-    debug!("{form_data:?}");
     state.db.create_user_token(form_data.clone()).await?;
     let raw_token = state.db.get_user_token(&form_data.token.unwrap()).await?;
-    Ok(Json(UserTokenHttpResponse {
-        user: MicroUser { name: user.name, avatar_url: "data/avatarka.jpg".to_string() }, // FIXME: hardcoded
-        token: raw_token.token,
-        note: raw_token.note,
-        enabled: raw_token.enabled,
-        expiration_time: raw_token.expiration_time,
-        creation_time: raw_token.creation_time,
-        last_edit_time: raw_token.last_edit_time,
-        last_usage_time: raw_token.last_usage_time,
-        version: raw_token.version,
-    }))
+    let user = MicroUser { avatar_url: get_avatar_url(&state.config.thumbnails, &user), name: user.name };
+    Ok(Json(user_token_answer(user, raw_token)))
+}
+
+pub async fn update_usertoken( // PUT
+    caller: CurrentUser,
+    Path((user, token)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<UpdateUserTokenHttpQuery>, // Must be last extractor
+) -> ApiResult<Json<UserTokenHttpResponse>> {
+    debug!("Trying to update user-token of '{user}' with params: {params:?}");
+    let privileges = &state.config.privileges;
+    caller.require_for(&user, privileges.user_tokens_edit_self, privileges.user_tokens_edit_any)?;
+    let user = state.db.get_user_by_name(&user).await?;
+    let raw_token = state.db.get_user_token(&token).await?;
+    if raw_token.user_id != user.id {
+        return Err(ApiError::UserTokenNotFound(format!("User token {token:?} not found.")));
+    }
+    check_version(raw_token.version, params.version)?;
+
+    let mut form_data: user_token::ActiveModel = raw_token.clone().into();
+    if let Some(note) = params.note {
+        form_data.note = Set(Some(note));
+    }
+    if let Some(enabled) = params.enabled {
+        form_data.enabled = Set(enabled);
+    }
+    if let Some(expiration_time) = params.expiration_time {
+        validate_expiration(&state, expiration_time)?;
+        form_data.expiration_time = Set(Some(expiration_time.naive_utc()));
+    }
+
+    let raw_token = state.db.update_user_token(raw_token.id as u64, params.version, form_data).await?;
+    let user = MicroUser { avatar_url: get_avatar_url(&state.config.thumbnails, &user), name: user.name };
+    Ok(Json(user_token_answer(user, raw_token)))
 }
 
 pub async fn delete_usertoken(
+    caller: CurrentUser,
     Path((user, token)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
 ) -> ApiResult<&'static str> {
+    let privileges = &state.config.privileges;
+    caller.require_for(&user, privileges.user_tokens_delete_self, privileges.user_tokens_delete_any)?;
     if state.db.get_user_by_name(&user).await?.id == state.db.get_user_token(&token).await?.user_id {
         state.db.delete_user_token(&token).await?;
-        debug!("Token {token} deleted!")
+        debug!("Token of '{user}' deleted!")
     } else {
         return Err(ApiError::DeleteToken(crate::db::errors::DeleteUserTokenError::TokenUserIdDontMatch));
     }
@@ -95,37 +160,25 @@ pub async fn delete_usertoken(
     Ok("{}")
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ListUserTokensHttpResponse {
-    pub results: Vec<UserTokenHttpResponse>,
-}
-
 pub async fn list_usertokens( // GET
+    caller: CurrentUser,
     Path(user): Path<String>,
+    Query(params): Query<PageParams>,
     State(state): State<Arc<AppState>>,
-) -> ApiResult<Json<ListUserTokensHttpResponse>> {
+) -> ApiResult<Json<PagedResponse<Sparse<UserTokenHttpResponse>>>> {
+    let privileges = &state.config.privileges;
+    caller.require_for(&user, privileges.user_tokens_list_self, privileges.user_tokens_list_any)?;
     let user = state.db.get_user_by_name(&user).await?;
-    let raw_tokens = state.db.get_user_tokens_by_user_id(user.id as u64).await?;
+    let page = params.page();
+    let raw_tokens = state.db.get_user_tokens_in_page(user.id as u64, page).await?;
     let miniuser = MicroUser {
+        avatar_url: get_avatar_url(&state.config.thumbnails, &user),
         name: user.name,
-        avatar_url: "data/avatarka.jpg".to_string(), // FIXME: hardcoded
     };
-    let mut prepared_tokens: Vec<UserTokenHttpResponse> = Vec::new();
-    for model in raw_tokens.iter() {
-        prepared_tokens.push(UserTokenHttpResponse {
-            user: miniuser.clone(),
-            token: model.token.to_owned(),
-            note: model.note.to_owned(),
-            enabled: model.enabled,
-            expiration_time: model.expiration_time,
-            creation_time: model.creation_time,
-            last_edit_time: model.last_edit_time,
-            last_usage_time: model.last_usage_time,
-            version: model.version,
-        })
+    let fields = Fields::parse(params.fields.as_deref());
+    let mut prepared_tokens: Vec<Sparse<UserTokenHttpResponse>> = Vec::new();
+    for model in raw_tokens.results.into_iter() {
+        prepared_tokens.push(fields.sparse(user_token_answer(miniuser.clone(), model)))
     }
-    Ok(Json(ListUserTokensHttpResponse {
-        results: prepared_tokens,
-    }))
+    Ok(Json(PagedResponse::new(params.query, page, raw_tokens.total, prepared_tokens)))
 }
\ No newline at end of file
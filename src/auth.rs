@@ -1,12 +1,22 @@
+use std::{str::FromStr, sync::Arc};
 use axum::{
     extract::FromRequestParts,
-    http::{header, StatusCode, request::Parts},
+    http::{header, request::Parts},
     async_trait,
 };
 use log::debug;
 use data_encoding::BASE64;
+use chrono::Utc;
+use sea_orm::Set;
 
-#[derive(PartialEq, Debug)]
+use crate::{
+    db::{errors::{DatabaseError, GetUserError, GetUserTokenError, UpdateError}, schemas::user},
+    error::{ApiError, AuthError},
+    func::auth::{hash_password, needs_rehash, verify_password, CURRENT_PASSWORD_REVISION},
+    AppState, UserRank,
+};
+
+#[derive(PartialEq, Debug, Clone, Default)]
 pub enum RequireAuth {
     Basic {
         name: String,
@@ -16,15 +26,10 @@ pub enum RequireAuth {
         name: String,
         token: String,
     },
+    #[default]
     None,
 }
 
-impl Default for RequireAuth {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl RequireAuth {
     pub fn is_some(&self) -> bool {
         !matches!(*self, RequireAuth::None)
@@ -34,73 +39,220 @@ impl RequireAuth {
     }
 }
 
-// pub struct User {
-//     pub id: i32,
-//     pub name: String,
-//     pub rank: UserRank,
-// }
+/// Authenticated user behind the request, `None` for anonymous requests.
+/// Used to check `Privileges` in handlers.
+#[derive(Debug, Clone, Default)]
+pub struct CurrentUser(pub Option<user::Model>);
+
+impl CurrentUser {
+    pub fn rank(&self) -> UserRank {
+        match &self.0 {
+            Some(user) => UserRank::from_str(&user.rank).unwrap_or(UserRank::Nobody),
+            None => UserRank::Anonymous,
+        }
+    }
+    pub fn id(&self) -> Option<i32> {
+        self.0.as_ref().map(|user| user.id)
+    }
+    /// Whether request is made by user with this name.
+    pub fn is(&self, name: &str) -> bool {
+        self.0.as_ref().is_some_and(|user| user.name == name)
+    }
+    /// Rejects request if caller rank is lower than required privilege.
+    /// Privilege set to `nobody` turns the action off for everyone.
+    pub fn require(&self, privilege: UserRank) -> Result<(), AuthError> {
+        if privilege != UserRank::Nobody && self.rank() >= privilege {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientPrivileges)
+        }
+    }
+    /// Picks `self` or `any` variant of privilege depending on whose resource is touched.
+    pub fn require_for(&self, name: &str, self_privilege: UserRank, any_privilege: UserRank) -> Result<(), AuthError> {
+        if self.is(name) {
+            self.require(self_privilege)
+        } else {
+            self.require(any_privilege)
+        }
+    }
+}
+
+/// Decodes base64 encoded `name:secret` pair from Authorization header.
+fn decode_credentials(auth: &str) -> Result<(String, String), AuthError> {
+    let decoded = BASE64.decode(auth.as_bytes()).map_err(|_| AuthError::MalformedHeader)?;
+    let decoded = String::from_utf8(decoded).map_err(|_| AuthError::MalformedHeader)?;
+    let (name, secret) = decoded.split_once(':').ok_or(AuthError::MalformedHeader)?;
+    Ok((name.to_string(), secret.to_string()))
+}
+
+/// Hashes password for the current revision. Argon2 is slow by design,
+/// so it runs outside of the async runtime.
+pub async fn hash_new_password(state: &AppState, password: &str) -> Result<(String, String), DatabaseError> {
+    let (password, secret) = (password.to_string(), state.config.secret.clone());
+    tokio::task::spawn_blocking(move || hash_password(&password, &secret))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+        .map_err(DatabaseError::from)
+}
+
+/// Checks name and password against the user table.
+/// Legacy password hashes are upgraded to the current revision on success.
+async fn authenticate_basic(state: &AppState, name: &str, password: &str) -> Result<user::Model, ApiError> {
+    let user = match state.db.get_user_by_name(name).await {
+        Err(GetUserError::UserNotFound { .. }) => return Err(AuthError::InvalidCredentials.into()),
+        user => user?,
+    };
+    let (checked_user, checked_password, secret) = (user.clone(), password.to_string(), state.config.secret.clone());
+    // Argon2 is slow by design, so don't block the runtime with it.
+    let valid = tokio::task::spawn_blocking(move || verify_password(&checked_user, &checked_password, &secret))
+        .await
+        .map_err(|e| DatabaseError::from(anyhow::Error::from(e)))?;
+    if !valid {
+        return Err(AuthError::InvalidCredentials.into());
+    }
+    if !needs_rehash(&user) {
+        return Ok(user);
+    }
+    debug!("Upgrading password hash of {} from revision {}", user.name, user.password_revision);
+    let (password_hash, password_salt) = hash_new_password(state, password).await?;
+    let mut form_data: user::ActiveModel = user.clone().into();
+    form_data.password_hash = Set(password_hash);
+    form_data.password_salt = Set(Some(password_salt));
+    form_data.password_revision = Set(CURRENT_PASSWORD_REVISION);
+    match state.db.update_user(user.id as u64, user.version, form_data).await {
+        // User was edited meanwhile, hash gets upgraded on next login
+        Err(UpdateError::VersionMismatch) => Ok(user),
+        result => Ok(result?),
+    }
+}
+
+/// Resolves login token, checks that it belongs to user and is still usable.
+async fn authenticate_token(state: &AppState, name: &str, token: &str) -> Result<user::Model, ApiError> {
+    let user = match state.db.get_user_by_name(name).await {
+        Err(GetUserError::UserNotFound { .. }) => return Err(AuthError::InvalidToken.into()),
+        user => user?,
+    };
+    let user_token = match state.db.get_user_token(token).await {
+        Err(GetUserTokenError::TokenNotFound { .. }) => return Err(AuthError::InvalidToken.into()),
+        user_token => user_token?,
+    };
+    if user_token.user_id != user.id {
+        return Err(AuthError::InvalidToken.into());
+    }
+    if !user_token.enabled {
+        return Err(AuthError::DisabledToken.into());
+    }
+    // Expiration time is stored in UTC, see api::usertoken::create_usertoken
+    if user_token.expiration_time.is_some_and(|time| time < Utc::now().naive_utc()) {
+        return Err(AuthError::ExpiredToken.into());
+    }
+    state.db.update_last_token_usage_time(token).await?;
+    Ok(user)
+}
 
 #[async_trait]
-impl<S> FromRequestParts<S> for RequireAuth
-where
-    S: Send + Sync,
-{
-    type Rejection = StatusCode;
+impl FromRequestParts<Arc<AppState>> for RequireAuth {
+    type Rejection = ApiError;
 
-    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        // Already verified by the auth layer, don't check credentials twice.
+        if let Some(auth) = parts.extensions.get::<RequireAuth>() {
+            return Ok(auth.clone());
+        }
         let auth_header = parts
             .headers
             .get(header::AUTHORIZATION)
             .and_then(|value| value.to_str().ok());
         // let auth_cookie; TODO: Узнать на счёт использования кукесов для авторизации
-        debug!("!AUTH {auth_header:?}");
 
-        if auth_header.is_none()  {
-            debug!("Don't have Auth: {auth_header:?}");
+        let Some(auth_header) = auth_header else {
+            debug!("Don't have Auth");
             return Ok(Self::default())
-        }
-        let (metod, auth) = match auth_header.unwrap().split_once(' ') {
+        };
+        let (metod, auth) = match auth_header.split_once(' ') {
             Some((metod, auth)) => (metod, auth),
-            None => ("", auth_header.unwrap()), // В метод ничего, а в ауз то что было в голове.
+            None => ("", auth_header), // В метод ничего, а в ауз то что было в голове.
         };
+        // Credentials themselves are never logged
+        debug!("!AUTH {metod}");
 
-        match metod {
+        let result = match metod {
             "Basic" => {
-                let auth = String::from_utf8(BASE64.decode(auth.as_bytes()).unwrap()).unwrap();
-                let (name, password) = auth.split_once(':').unwrap();
-                debug!("!BASIC {name} {password}");
-                Ok(Self::Basic {name: name.to_string(), password: password.to_string()})
+                let (name, password) = decode_credentials(auth)?;
+                debug!("!BASIC {name}");
+                let user = authenticate_basic(state, &name, &password).await?;
+                parts.extensions.insert(user);
+                Self::Basic { name, password }
             }
             "Token" => {
-                let auth = String::from_utf8(BASE64.decode(auth.as_bytes()).unwrap()).unwrap();
-                let (name, token) = auth.split_once(':').unwrap();
-                debug!("!TOKEN {name} {token}");
-                Ok(Self::Token { name: name.to_string(), token: token.to_string() })
+                let (name, token) = decode_credentials(auth)?;
+                debug!("!TOKEN {name}");
+                let user = authenticate_token(state, &name, &token).await?;
+                parts.extensions.insert(user);
+                Self::Token { name, token }
             }
             _ => { // Попадём сюда в случае если метод пустой, когда там неверные данные например, или если что-то другое.
                 // Boom!
-                debug!("Something other in auth: {auth}");
-                Ok(Self::default())
+                debug!("Something other in auth: {metod:?}");
+                Self::default()
             }
-        }
+        };
+        parts.extensions.insert(result.clone());
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for CurrentUser {
+    type Rejection = ApiError;
 
-        // debug!("Auth: {auth_header:?}");
-        // Ok(Self::default())
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        // Verified user lands in extensions while extracting RequireAuth
+        RequireAuth::from_request_parts(parts, state).await?;
+        Ok(Self(parts.extensions.get::<user::Model>().cloned()))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::RequireAuth;
+    use crate::{db::schemas::user, CurrentUser, RequireAuth, UserRank};
 
     #[test]
     fn is_some() {
         let x = RequireAuth::Basic { name: "".to_string(), password: "".to_string() };
-        assert_eq!(x.is_some(), true)
+        assert!(x.is_some())
     }
     #[test]
     fn is_none() {
         let x = RequireAuth::None;
-        assert_eq!(x.is_none(), true)
+        assert!(x.is_none())
     }
-}
\ No newline at end of file
+    #[test]
+    fn anonymous_rank() {
+        let x = CurrentUser(None);
+        assert_eq!(x.rank(), UserRank::Anonymous);
+        assert!(x.require(UserRank::Anonymous).is_ok());
+        assert!(x.require(UserRank::Regular).is_err());
+        assert!(UserRank::Nobody < UserRank::Anonymous && UserRank::Moderator < UserRank::Administrator);
+    }
+    #[test]
+    fn nobody_privilege() {
+        let admin = CurrentUser(Some(user::Model {
+            id: 1,
+            name: "admin".to_string(),
+            password_hash: String::new(),
+            password_salt: None,
+            email: None,
+            rank: "administrator".to_string(),
+            creation_time: chrono::Local::now().naive_local(),
+            last_login_time: None,
+            avatar_style: "gravatar".to_string(),
+            version: 1,
+            password_revision: 3,
+        }));
+        assert!(admin.require(UserRank::Administrator).is_ok());
+        assert!(admin.require(UserRank::Nobody).is_err());
+        assert!(CurrentUser(None).require(UserRank::Nobody).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{io::Read, path::PathBuf};
+use std::{collections::HashMap, io::Read, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +23,10 @@ pub struct Config {
     pub webhooks: Option<Vec<String>>,
     pub default_rank: UserRank,
     pub thumbnails: Thumbnails,
+    #[serde(default)]
+    pub tokens: Tokens,
+    #[serde(default)]
+    pub limits: Limits,
     pub smtp: Smtp,
     pub privileges: Privileges,
 }
@@ -35,6 +39,54 @@ pub struct Thumbnails {
     pub post_height: u64,
 }
 
+/// User token lifetimes are in days.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Tokens {
+    pub default_lifetime: u32,
+    pub max_lifetime: Option<u32>,
+    pub max_per_user: Option<u64>,
+}
+
+impl Default for Tokens {
+    fn default() -> Self {
+        Self {
+            default_lifetime: 365,
+            max_lifetime: None,
+            max_per_user: None,
+        }
+    }
+}
+
+/// Upload sizes are in bytes.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Limits {
+    pub max_upload_size: usize,
+    /// Overrides `max_upload_size` for given MIME types
+    #[serde(default)]
+    pub max_upload_size_by_mime: HashMap<String, usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_upload_size: 1073741824, // 1 GB
+            max_upload_size_by_mime: HashMap::new(),
+        }
+    }
+}
+
+impl Limits {
+    pub fn max_upload_size_for(&self, mime: &str) -> usize {
+        self.max_upload_size_by_mime.get(mime).copied().unwrap_or(self.max_upload_size)
+    }
+    /// Largest upload allowed for any MIME type, used as request body limit.
+    pub fn largest_upload_size(&self) -> usize {
+        self.max_upload_size_by_mime.values().copied().fold(self.max_upload_size, usize::max)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Smtp {
     pub enabled: bool,
@@ -249,3 +301,31 @@ impl Config {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Limits {
+        Limits {
+            max_upload_size: 100,
+            max_upload_size_by_mime: HashMap::from([("image/png".to_string(), 50), ("video/mp4".to_string(), 500)]),
+        }
+    }
+
+    #[test]
+    fn max_upload_size_for() {
+        let limits = limits();
+        assert_eq!(limits.max_upload_size_for("image/png"), 50);
+        assert_eq!(limits.max_upload_size_for("video/mp4"), 500);
+        assert_eq!(limits.max_upload_size_for("image/gif"), 100);
+    }
+    #[test]
+    fn largest_upload_size() {
+        assert_eq!(limits().largest_upload_size(), 500);
+        let mut limits = limits();
+        limits.max_upload_size_by_mime.remove("video/mp4");
+        assert_eq!(limits.largest_upload_size(), 100);
+        assert_eq!(Limits::default().largest_upload_size(), 1073741824);
+    }
+}
@@ -20,6 +20,12 @@ pub struct Upload {
     pub content_type: String,
 }
 
+impl Upload {
+    pub fn path(&self) -> String {
+        format!("{TEMP}/{}", self.filename)
+    }
+}
+
 impl Data {
     pub fn new() -> Self {
         Self::default()
@@ -57,6 +63,9 @@ impl Data {
     pub fn vec(&self) -> Vec<(String, Upload)> {
         self.0.clone().into_iter().collect()
     }
+    pub fn get(&self, token: &str) -> Option<Upload> {
+        Some(self.0.get(token)?.to_owned())
+    }
     pub fn get_and_remove(&self, token: &str) -> Option<Upload> {
         Some(self.0.remove(token)?.to_owned().1)
     }
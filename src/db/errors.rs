@@ -5,13 +5,49 @@ pub struct DatabaseError {
     source: anyhow::Error,
 }
 
+impl DatabaseError {
+    /// Whether write was rejected by unique index, e.g. name taken meanwhile.
+    pub fn is_unique_violation(&self) -> bool {
+        let error = self.source.downcast_ref::<sea_orm::DbErr>().and_then(|e| e.sql_err());
+        matches!(error, Some(sea_orm::SqlErr::UniqueConstraintViolation(_)))
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetUserError {
-    #[error("There is no user with {name:?} as name.")]
+    #[error("User {user:?} not found.")]
     UserNotFound {
-        name: String,
-        #[source]
-        source: DatabaseError
+        user: String,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetPostError {
+    #[error("Post {id} not found.")]
+    PostNotFound {
+        id: u64,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetCommentError {
+    #[error("Comment {id} not found.")]
+    CommentNotFound {
+        id: u64,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetUserTokenError {
+    #[error("User token {token:?} not found.")]
+    TokenNotFound {
+        token: String,
     },
     #[error("Something went wrong.")]
     DatabaseError(#[from] DatabaseError),
@@ -19,14 +55,60 @@ pub enum GetUserError {
 
 #[derive(thiserror::Error, Debug)]
 pub enum DeleteUserTokenError {
-    #[error("There is no user_token with {token:?} as token.")]
+    #[error("User token {token:?} not found.")]
     TokenNotFound {
         token: String,
-        #[source]
-        source: DatabaseError
     },
     #[error("Token user_id and user doesn't match!")]
     TokenUserIdDontMatch,
     #[error("Something went wrong.")]
     DatabaseError(#[from] DatabaseError),
-}
\ No newline at end of file
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTagError {
+    #[error("Tag {name:?} not found.")]
+    TagNotFound {
+        name: String,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTagCategoryError {
+    #[error("Tag category {name:?} not found.")]
+    TagCategoryNotFound {
+        name: String,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetPoolError {
+    #[error("Pool {id} not found.")]
+    PoolNotFound {
+        id: u64,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetPoolCategoryError {
+    #[error("Pool category {name:?} not found.")]
+    PoolCategoryNotFound {
+        name: String,
+    },
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UpdateError {
+    #[error("Someone else modified this in the meantime. Please try again.")]
+    VersionMismatch,
+    #[error("Something went wrong.")]
+    DatabaseError(#[from] DatabaseError),
+}
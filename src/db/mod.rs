@@ -1,3 +1,4 @@
 pub mod schemas;
 pub mod repository;
-pub mod errors;
\ No newline at end of file
+pub mod errors;
+pub mod search;
\ No newline at end of file
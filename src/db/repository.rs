@@ -1,16 +1,120 @@
+use std::collections::HashMap;
 use sea_orm::*;
-use chrono::Local;
+use sea_orm::sea_query::{Expr, Func, Query};
+use chrono::{Local, Utc};
 
 use crate::db::schemas::{
     prelude::*,
-    user, user_token, post, snapshot,
+    user, user_token, comment, comment_score, pool, pool_category, pool_name, pool_post, post, post_favorite, post_note, post_relation, post_score, snapshot, tag, tag_name, post_tag, tag_category, tag_implication, tag_suggestion,
 };
 use super::errors::*;
+use super::search::like_pattern;
+use crate::func::search::{CommentSearch, PostSearch};
 
 pub fn to_db_error(e: sea_orm::DbErr) -> DatabaseError {
     DatabaseError::from(anyhow::Error::from(e))
 }
 
+/// Optimistic locking: applies `model` to row `id` only while the row is still at `version`.
+async fn update_versioned<E, A, C>(db: &C, model: A, id: (E::Column, i32), version: (E::Column, i32)) -> Result<E::Model, UpdateError>
+where
+    E: EntityTrait,
+    A: ActiveModelTrait<Entity = E>,
+    C: ConnectionTrait,
+{
+    let result = E::update_many()
+        .set(model)
+        .filter(id.0.eq(id.1))
+        .filter(version.0.eq(version.1))
+        .exec(db)
+        .await.map_err(to_db_error)?;
+    if result.rows_affected == 0 {
+        return Err(UpdateError::VersionMismatch);
+    }
+    E::find()
+        .filter(id.0.eq(id.1))
+        .one(db)
+        .await.map_err(to_db_error)?
+        .ok_or_else(|| DatabaseError::from(anyhow::anyhow!("Updated row not found")).into())
+}
+
+/// Re-points `(parent_id, child_id)` pairs of `source` to `target`,
+/// skipping self-relations and pairs `target` already has.
+fn repoint_tag_pairs(pairs: &[(i32, i32)], source: i32, target: i32) -> Vec<(i32, i32)> {
+    let mut result: Vec<(i32, i32)> = Vec::new();
+    for (parent_id, child_id) in pairs.iter().filter(|(parent_id, child_id)| *parent_id == source || *child_id == source) {
+        let swap = |id: i32| if id == source { target } else { id };
+        let pair = (swap(*parent_id), swap(*child_id));
+        if pair.0 != pair.1 && !pairs.contains(&pair) && !result.contains(&pair) {
+            result.push(pair);
+        }
+    }
+    result
+}
+
+/// Tag names are unique regardless of case.
+fn lower_tag_name() -> Expr {
+    Expr::expr(Func::lower(Expr::col((TagName, tag_name::Column::Name))))
+}
+
+/// Pool names are unique regardless of case.
+fn lower_pool_name() -> Expr {
+    Expr::expr(Func::lower(Expr::col((PoolName, pool_name::Column::Name))))
+}
+
+pub const DEFAULT_PAGE_LIMIT: u64 = 100;
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
+/// Requested window of a listing. When `after` is given, rows are taken
+/// by keyset on id instead of offset, so deep pages of large tables stay cheap.
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    pub offset: u64,
+    pub limit: u64,
+    pub after: Option<i32>,
+}
+
+impl PageRequest {
+    pub fn new(offset: Option<u64>, limit: Option<u64>, after: Option<i32>) -> Self {
+        Self {
+            offset: offset.unwrap_or_default(),
+            limit: limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT),
+            after,
+        }
+    }
+}
+
+/// Rows of a listing together with count of every matching row.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub total: u64,
+    pub results: Vec<T>,
+}
+
+/// Existing tags and `new_names` of tags created in `category` along with
+/// the post or tag referring to them.
+#[derive(Debug, Clone, Default)]
+pub struct PlannedTags {
+    pub tag_ids: Vec<i32>,
+    pub new_names: Vec<String>,
+    pub category: String,
+}
+
+/// Rows belonging to a post, each is replaced in the same transaction as the post when given.
+#[derive(Debug, Default)]
+pub struct PostRelated {
+    pub tags: Option<PlannedTags>,
+    pub relations: Option<Vec<i32>>,
+    pub notes: Option<Vec<post_note::ActiveModel>>,
+}
+
+/// Relations of a tag, each is replaced in the same transaction as the tag when given.
+#[derive(Debug, Default)]
+pub struct TagRelated {
+    pub implications: Option<PlannedTags>,
+    pub suggestions: Option<PlannedTags>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Repository(DatabaseConnection);
 
@@ -27,29 +131,50 @@ impl Repository {
     pub fn pool(&self) -> DatabaseConnection {
         self.0.clone()
     }
+    /// Fetches page of already ordered `select`. Order must end with `id`
+    /// in direction given by `descending` for keyset to be correct.
+    async fn fetch_page<E>(&self, select: Select<E>, id: E::Column, descending: bool, page: PageRequest) -> Result<Page<E::Model>, DatabaseError>
+    where
+        E: EntityTrait,
+        E::Model: Send + Sync,
+    {
+        let total = select.clone().count(&self.0).await.map_err(to_db_error)?;
+        let select = match page.after {
+            Some(after) if descending => select.filter(id.lt(after)),
+            Some(after) => select.filter(id.gt(after)),
+            None => select.offset(page.offset),
+        };
+        let results = select.limit(page.limit).all(&self.0).await.map_err(to_db_error)?;
+        Ok(Page { total, results })
+    }
     // User
     pub async fn get_users_count(&self) -> Result<u64, DatabaseError> {
         User::find().count(&self.0).await.map_err(to_db_error)
     }
-    pub async fn get_users_in_page(&self, page: u64, per_page: u64) -> Result<(Vec<user::Model>, u64), DatabaseError> {
-        // Setup paginator
-        let paginator = User::find()
-            .order_by_asc(user::Column::Id)
-            .paginate(&self.0, per_page);
-        let num_pages = paginator.num_pages().await.map_err(to_db_error)?;
-        // Fetch paginator users
-        paginator.fetch_page(page - 1).await.map_err(to_db_error).map(|p| (p, num_pages))
+    /// `name_pattern` may contain `*` wildcards, matched regardless of case.
+    pub async fn get_users_in_page(&self, name_pattern: Option<&str>, page: PageRequest) -> Result<Page<user::Model>, DatabaseError> {
+        let mut select = User::find();
+        if let Some(pattern) = name_pattern {
+            select = select.filter(Expr::expr(Func::lower(Expr::col(user::Column::Name))).like(like_pattern(pattern)));
+        }
+        let select = select.order_by_asc(user::Column::Id);
+        self.fetch_page(select, user::Column::Id, false, page).await
     }
     pub async fn get_user_by_id(&self, id: u64) -> Result<user::Model, GetUserError> {
-        Ok(User::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("User not found"))})?)
+        User::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| GetUserError::UserNotFound { user: id.to_string() })
+    }
+    pub async fn get_users_by_ids(&self, ids: &[i32]) -> Result<Vec<user::Model>, DatabaseError> {
+        User::find()
+            .filter(user::Column::Id.is_in(ids.to_vec()))
+            .all(&self.0)
+            .await.map_err(to_db_error)
     }
     pub async fn get_user_by_name(&self, name: &str) -> Result<user::Model, GetUserError> {
-        let user = User::find()
-            .filter(user::Column::Name.contains(name))
+        User::find()
+            .filter(user::Column::Name.eq(name))
             .one(&self.0)
             .await.map_err(to_db_error)?
-            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("User not found"))})?;
-        Ok(user)
+            .ok_or_else(|| GetUserError::UserNotFound { user: name.to_string() })
     }
     pub async fn create_user(&self, user: user::ActiveModel) -> Result<user::ActiveModel, DatabaseError> {
         user::ActiveModel {
@@ -61,28 +186,21 @@ impl Repository {
         .save(&self.0)
         .await.map_err(to_db_error)
     }
-    pub async fn update_user(&self, id: u64, user: user::ActiveModel) -> Result<user::Model, DatabaseError> {
-        let current_user: user::ActiveModel = User::find_by_id(id as i32)
-            .one(&self.0)
-            .await.map_err(to_db_error)?
-            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("User not found"))})
-            .map(Into::into)?;
+    /// `version` is the version edit was made against, fails if user changed since then.
+    pub async fn update_user(&self, id: u64, version: i32, user: user::ActiveModel) -> Result<user::Model, UpdateError> {
         let user = user.try_into_model().expect("Can't into model");
-        user::ActiveModel {
-            id: current_user.id,
-            name: current_user.name,
-            password_hash: Set(user.password_hash.to_owned()),
-            password_salt: Set(user.password_salt.to_owned()),
-            email: Set(user.email.to_owned()),
-            rank: Set(user.rank.to_owned()),
-            creation_time: current_user.creation_time,
-            last_login_time: current_user.last_login_time,
-            avatar_style: Set(user.avatar_style.to_owned()),
-            version: Set(user.version.to_owned()),
-            password_revision: Set(user.password_revision.to_owned()),
-        }
-        .update(&self.0)
-        .await.map_err(to_db_error)
+        let user = user::ActiveModel {
+            name: Set(user.name.to_owned()),                            // Can be updated
+            password_hash: Set(user.password_hash.to_owned()),          // Can be updated
+            password_salt: Set(user.password_salt.to_owned()),          // Can be updated
+            email: Set(user.email.to_owned()),                          // Can be updated
+            rank: Set(user.rank.to_owned()),                            // Can be updated
+            avatar_style: Set(user.avatar_style.to_owned()),            // Can be updated
+            version: Set(version + 1),
+            password_revision: Set(user.password_revision.to_owned()),  // Can be updated
+            ..Default::default()
+        };
+        update_versioned(&self.0, user, (user::Column::Id, id as i32), (user::Column::Version, version)).await
     }
     pub async fn delete_user(&self, id: u64) -> Result<(), DatabaseError> {
         let user: user::ActiveModel = User::find_by_id(id as i32)
@@ -96,7 +214,7 @@ impl Repository {
     }
     pub async fn update_last_login_time(&self, name: &str) -> Result<user::Model, DatabaseError> {
         let mut current_user: user::ActiveModel = User::find()
-            .filter(user::Column::Name.contains(name))
+            .filter(user::Column::Name.eq(name))
             .one(&self.0)
             .await.map_err(to_db_error)?
             .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("User not found"))})
@@ -108,52 +226,74 @@ impl Repository {
     pub async fn get_posts_count(&self) -> Result<u64, DatabaseError> {
         Post::find().count(&self.0).await.map_err(to_db_error)
     }
-    pub async fn get_posts_in_page(&self, page: u64, per_page: u64) -> Result<(Vec<post::Model>, u64), DatabaseError> {
-        // Setup paginator
-        let paginator = Post::find()
-            .order_by_asc(post::Column::Id)
-            .paginate(&self.0, per_page);
-        let num_pages = paginator.num_pages().await.map_err(to_db_error)?;
-        // Fetch paginator posts
-        paginator.fetch_page(page).await.map_err(to_db_error).map(|p| (p, num_pages))
+    /// Keyset `page.after` is only correct with sorting by id.
+    pub async fn search_posts(&self, search: &PostSearch, user_id: Option<i32>, page: PageRequest) -> Result<Page<post::Model>, DatabaseError> {
+        let select = super::search::order_posts(super::search::filter_posts(search, user_id), search);
+        self.fetch_page(select, post::Column::Id, !search.ascending, page).await
     }
-    pub async fn get_post_by_id(&self, id: u64) -> Result<post::Model, DatabaseError> {
-        Ok(Post::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Post not found"))})?)
+    pub async fn get_post_by_id(&self, id: u64) -> Result<post::Model, GetPostError> {
+        Post::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or(GetPostError::PostNotFound { id })
+    }
+    pub async fn get_posts_by_ids(&self, ids: &[i32]) -> Result<Vec<post::Model>, DatabaseError> {
+        Post::find()
+            .filter(post::Column::Id.is_in(ids.to_vec()))
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    pub async fn get_post_by_checksum(&self, checksum: &str) -> Result<Option<post::Model>, DatabaseError> {
+        Post::find()
+            .filter(post::Column::Checksum.eq(checksum))
+            .one(&self.0)
+            .await.map_err(to_db_error)
     }
-    pub async fn create_post(&self, post: post::ActiveModel) -> Result<post::ActiveModel, DatabaseError> {
-        post::ActiveModel {
+    pub async fn create_post(&self, post: post::ActiveModel, related: PostRelated) -> Result<post::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let post = post::ActiveModel {
             creation_time: Set(Local::now().naive_local().to_owned()),
             ..post
         }
-        .save(&self.0)
-        .await.map_err(to_db_error)
+        .insert(&txn)
+        .await.map_err(to_db_error)?;
+        Self::replace_post_related(&txn, post.id, related).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(post)
     }
-    pub async fn update_post(&self, id: u64, post: post::ActiveModel) -> Result<post::Model, DatabaseError> {
-        let posts: post::ActiveModel = Post::find_by_id(id as i32)
-            .one(&self.0)
-            .await.map_err(to_db_error)?
-            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Post not found"))})
-            .map(Into::into)?;
+    /// `version` is the version edit was made against, fails if post changed since then.
+    pub async fn update_post(&self, id: u64, version: i32, post: post::ActiveModel, related: PostRelated) -> Result<post::Model, UpdateError> {
         let post = post.try_into_model().expect("Can't into model");
-        post::ActiveModel {
-            id: posts.id,
-            user_id: posts.user_id,
-            creation_time: posts.creation_time,
+        let post = post::ActiveModel {
             last_edit_time: Set(Some(Local::now().naive_local().to_owned())),
-            safety: Set(post.safety.to_owned()),
-            r#type: Set(post.r#type.to_owned()),
-            checksum: Set(post.checksum.to_owned()),
-            source: posts.source,
-            file_size: posts.file_size,
-            image_width: posts.image_width,
-            image_height: posts.image_height,
-            mime_type: Set(post.mime_type.to_owned()),
-            version: Set(post.version.to_owned()),
-            flags: posts.flags,
-            checksum_md5: posts.checksum_md5,
-        }
-        .update(&self.0)
-        .await.map_err(to_db_error)
+            safety: Set(post.safety.to_owned()),                // Can be updated
+            r#type: Set(post.r#type.to_owned()),                // Can be updated
+            checksum: Set(post.checksum.to_owned()),            // Can be updated
+            source: Set(post.source.to_owned()),                // Can be updated
+            file_size: Set(post.file_size.to_owned()),          // Can be updated
+            image_width: Set(post.image_width.to_owned()),      // Can be updated
+            image_height: Set(post.image_height.to_owned()),    // Can be updated
+            mime_type: Set(post.mime_type.to_owned()),          // Can be updated
+            version: Set(version + 1),
+            flags: Set(post.flags.to_owned()),                  // Can be updated
+            checksum_md5: Set(post.checksum_md5.to_owned()),    // Can be updated
+            has_custom_thumbnail: Set(post.has_custom_thumbnail), // Can be updated
+            ..Default::default()
+        };
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let post = update_versioned(&txn, post, (post::Column::Id, id as i32), (post::Column::Version, version)).await?;
+        Self::replace_post_related(&txn, post.id, related).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(post)
+    }
+    async fn replace_post_related<C: ConnectionTrait>(db: &C, post_id: i32, related: PostRelated) -> Result<(), DbErr> {
+        if let Some(tags) = related.tags {
+            Self::replace_post_tags(db, post_id, tags).await?;
+        }
+        if let Some(relations) = related.relations {
+            Self::replace_post_relations(db, post_id, &relations).await?;
+        }
+        if let Some(notes) = related.notes {
+            Self::replace_post_notes(db, post_id, notes).await?;
+        }
+        Ok(())
     }
     pub async fn delete_post(&self, id: u64) -> Result<(), DatabaseError> {
         let post: post::ActiveModel = Post::find_by_id(id as i32)
@@ -164,31 +304,283 @@ impl Repository {
         post.delete(&self.0).await.map_err(to_db_error)?;
         Ok(())
     }
+    // Post Score
+    /// Sums of scores of given posts, posts without scores are absent.
+    pub async fn get_post_scores(&self, post_ids: &[i32]) -> Result<HashMap<i32, i64>, DatabaseError> {
+        let scores: Vec<(i32, i64)> = PostScore::find()
+            .select_only()
+            .column(post_score::Column::PostId)
+            .column_as(post_score::Column::Score.sum(), "score")
+            .filter(post_score::Column::PostId.is_in(post_ids.to_vec()))
+            .group_by(post_score::Column::PostId)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(scores.into_iter().collect())
+    }
+    pub async fn get_own_post_score(&self, post_id: i32, user_id: i32) -> Result<i32, DatabaseError> {
+        let score = PostScore::find_by_id((post_id, user_id)).one(&self.0).await.map_err(to_db_error)?;
+        Ok(score.map(|score| score.score).unwrap_or_default())
+    }
+    /// Returns `(liked, disliked)` post counts of user.
+    pub async fn get_user_score_counts(&self, user_id: i32) -> Result<(u64, u64), DatabaseError> {
+        let count = |score: i32| PostScore::find()
+            .filter(post_score::Column::UserId.eq(user_id))
+            .filter(post_score::Column::Score.eq(score))
+            .count(&self.0);
+        Ok((count(1).await.map_err(to_db_error)?, count(-1).await.map_err(to_db_error)?))
+    }
+    /// Zero score removes the existing one.
+    pub async fn set_post_score(&self, post_id: i32, user_id: i32, score: i32) -> Result<(), DatabaseError> {
+        if score == 0 {
+            PostScore::delete_by_id((post_id, user_id)).exec(&self.0).await.map_err(to_db_error)?;
+            return Ok(());
+        }
+        PostScore::insert(post_score::ActiveModel {
+            post_id: Set(post_id),
+            user_id: Set(user_id),
+            score: Set(score),
+            time: Set(Local::now().naive_local()),
+        })
+        .on_conflict(
+            sea_query::OnConflict::columns([post_score::Column::PostId, post_score::Column::UserId])
+                .update_columns([post_score::Column::Score, post_score::Column::Time])
+                .to_owned()
+        )
+        .exec(&self.0)
+        .await.map_err(to_db_error)?;
+        Ok(())
+    }
+    // Post Favorite
+    pub async fn get_post_favorite_counts(&self, post_ids: &[i32]) -> Result<HashMap<i32, i64>, DatabaseError> {
+        let counts: Vec<(i32, i64)> = PostFavorite::find()
+            .select_only()
+            .column(post_favorite::Column::PostId)
+            .column_as(post_favorite::Column::UserId.count(), "favorites")
+            .filter(post_favorite::Column::PostId.is_in(post_ids.to_vec()))
+            .group_by(post_favorite::Column::PostId)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(counts.into_iter().collect())
+    }
+    /// Users who favorited post, earliest first.
+    pub async fn get_post_favorited_by(&self, post_id: i32) -> Result<Vec<user::Model>, DatabaseError> {
+        let found = PostFavorite::find()
+            .filter(post_favorite::Column::PostId.eq(post_id))
+            .order_by_asc(post_favorite::Column::Time)
+            .find_also_related(User)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().filter_map(|(_, user)| user).collect())
+    }
+    pub async fn get_user_favorite_count(&self, user_id: i32) -> Result<u64, DatabaseError> {
+        PostFavorite::find()
+            .filter(post_favorite::Column::UserId.eq(user_id))
+            .count(&self.0)
+            .await.map_err(to_db_error)
+    }
+    /// Adding already existing favorite does nothing.
+    pub async fn add_post_favorite(&self, post_id: i32, user_id: i32) -> Result<(), DatabaseError> {
+        PostFavorite::insert(post_favorite::ActiveModel {
+            post_id: Set(post_id),
+            user_id: Set(user_id),
+            time: Set(Local::now().naive_local()),
+        })
+        .on_conflict(
+            sea_query::OnConflict::columns([post_favorite::Column::PostId, post_favorite::Column::UserId])
+                .do_nothing()
+                .to_owned()
+        )
+        .exec_without_returning(&self.0)
+        .await.map_err(to_db_error)?;
+        Ok(())
+    }
+    pub async fn remove_post_favorite(&self, post_id: i32, user_id: i32) -> Result<(), DatabaseError> {
+        PostFavorite::delete_by_id((post_id, user_id)).exec(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
+    // Post Note
+    pub async fn get_post_notes(&self, post_id: i32) -> Result<Vec<post_note::Model>, DatabaseError> {
+        PostNote::find()
+            .filter(post_note::Column::PostId.eq(post_id))
+            .order_by_asc(post_note::Column::Id)
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    /// Replaces every note of post.
+    async fn replace_post_notes<C: ConnectionTrait>(db: &C, post_id: i32, notes: Vec<post_note::ActiveModel>) -> Result<(), DbErr> {
+        PostNote::delete_many()
+            .filter(post_note::Column::PostId.eq(post_id))
+            .exec(db)
+            .await?;
+        if !notes.is_empty() {
+            PostNote::insert_many(notes.into_iter().map(|note| post_note::ActiveModel {
+                post_id: Set(post_id),
+                ..note
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+    // Post Relation
+    /// Ids of posts related to post.
+    pub async fn get_post_relations(&self, post_id: i32) -> Result<Vec<i32>, DatabaseError> {
+        let found = PostRelation::find()
+            .filter(post_relation::Column::ParentId.eq(post_id))
+            .order_by_asc(post_relation::Column::ChildId)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().map(|relation| relation.child_id).collect())
+    }
+    /// Replaces every relation of post. Relations are stored in both
+    /// directions, so related posts gain and lose the post as well.
+    async fn replace_post_relations<C: ConnectionTrait>(db: &C, post_id: i32, related_ids: &[i32]) -> Result<(), DbErr> {
+        PostRelation::delete_many()
+            .filter(Condition::any()
+                .add(post_relation::Column::ParentId.eq(post_id))
+                .add(post_relation::Column::ChildId.eq(post_id)))
+            .exec(db)
+            .await?;
+        if !related_ids.is_empty() {
+            let pairs = related_ids.iter().flat_map(|related_id| [(post_id, *related_id), (*related_id, post_id)]);
+            PostRelation::insert_many(pairs.map(|(parent_id, child_id)| post_relation::ActiveModel {
+                parent_id: Set(parent_id),
+                child_id: Set(child_id),
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+    // Comment
+    /// Keyset `page.after` is only correct with sorting by id.
+    pub async fn search_comments(&self, search: &CommentSearch, page: PageRequest) -> Result<Page<comment::Model>, DatabaseError> {
+        let select = super::search::order_comments(super::search::filter_comments(search), search);
+        self.fetch_page(select, comment::Column::Id, !search.ascending, page).await
+    }
+    pub async fn get_comment_by_id(&self, id: u64) -> Result<comment::Model, GetCommentError> {
+        Comment::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or(GetCommentError::CommentNotFound { id })
+    }
+    /// Comments of post, oldest first.
+    pub async fn get_post_comments(&self, post_id: i32) -> Result<Vec<comment::Model>, DatabaseError> {
+        Comment::find()
+            .filter(comment::Column::PostId.eq(post_id))
+            .order_by_asc(comment::Column::Id)
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    pub async fn get_post_comment_counts(&self, post_ids: &[i32]) -> Result<HashMap<i32, i64>, DatabaseError> {
+        let counts: Vec<(i32, i64)> = Comment::find()
+            .select_only()
+            .column(comment::Column::PostId)
+            .column_as(comment::Column::Id.count(), "comments")
+            .filter(comment::Column::PostId.is_in(post_ids.to_vec()))
+            .group_by(comment::Column::PostId)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(counts.into_iter().collect())
+    }
+    pub async fn get_user_comment_count(&self, user_id: i32) -> Result<u64, DatabaseError> {
+        Comment::find()
+            .filter(comment::Column::UserId.eq(user_id))
+            .count(&self.0)
+            .await.map_err(to_db_error)
+    }
+    pub async fn create_comment(&self, comment: comment::ActiveModel) -> Result<comment::Model, DatabaseError> {
+        comment::ActiveModel {
+            creation_time: Set(Local::now().naive_local().to_owned()),
+            last_edit_time: Set(None),
+            version: Set(1),
+            ..comment
+        }
+        .insert(&self.0)
+        .await.map_err(to_db_error)
+    }
+    /// `version` is the version edit was made against, fails if comment changed since then.
+    pub async fn update_comment(&self, id: u64, version: i32, comment: comment::ActiveModel) -> Result<comment::Model, UpdateError> {
+        let comment = comment.try_into_model().expect("Can't into model");
+        let comment = comment::ActiveModel {
+            last_edit_time: Set(Some(Local::now().naive_local().to_owned())),
+            text: Set(comment.text.to_owned()),          // Can be updated
+            version: Set(version + 1),
+            ..Default::default()
+        };
+        update_versioned(&self.0, comment, (comment::Column::Id, id as i32), (comment::Column::Version, version)).await
+    }
+    pub async fn delete_comment(&self, id: u64) -> Result<(), DatabaseError> {
+        Comment::delete_by_id(id as i32).exec(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
+    // Comment Score
+    /// Sums of scores of given comments, comments without scores are absent.
+    pub async fn get_comment_scores(&self, comment_ids: &[i32]) -> Result<HashMap<i32, i64>, DatabaseError> {
+        let scores: Vec<(i32, i64)> = CommentScore::find()
+            .select_only()
+            .column(comment_score::Column::CommentId)
+            .column_as(comment_score::Column::Score.sum(), "score")
+            .filter(comment_score::Column::CommentId.is_in(comment_ids.to_vec()))
+            .group_by(comment_score::Column::CommentId)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(scores.into_iter().collect())
+    }
+    /// Scores given by user to comments, unscored comments are absent.
+    pub async fn get_own_comment_scores(&self, comment_ids: &[i32], user_id: i32) -> Result<HashMap<i32, i32>, DatabaseError> {
+        let scores = CommentScore::find()
+            .filter(comment_score::Column::CommentId.is_in(comment_ids.to_vec()))
+            .filter(comment_score::Column::UserId.eq(user_id))
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(scores.into_iter().map(|score| (score.comment_id, score.score)).collect())
+    }
+    /// Zero score removes the existing one.
+    pub async fn set_comment_score(&self, comment_id: i32, user_id: i32, score: i32) -> Result<(), DatabaseError> {
+        if score == 0 {
+            CommentScore::delete_by_id((comment_id, user_id)).exec(&self.0).await.map_err(to_db_error)?;
+            return Ok(());
+        }
+        CommentScore::insert(comment_score::ActiveModel {
+            comment_id: Set(comment_id),
+            user_id: Set(user_id),
+            score: Set(score),
+            time: Set(Local::now().naive_local()),
+        })
+        .on_conflict(
+            sea_query::OnConflict::columns([comment_score::Column::CommentId, comment_score::Column::UserId])
+                .update_columns([comment_score::Column::Score, comment_score::Column::Time])
+                .to_owned()
+        )
+        .exec(&self.0)
+        .await.map_err(to_db_error)?;
+        Ok(())
+    }
     // User Token
-    pub async fn get_user_tokens_count(&self) -> Result<u64, DatabaseError> {
-        UserToken::find().count(&self.0).await.map_err(to_db_error)
-    }
-    pub async fn get_user_tokens_in_page(&self, page: u64, per_page: u64) -> Result<(Vec<user_token::Model>, u64), DatabaseError> {
-        // Setup paginator
-        let paginator = UserToken::find()
-            .order_by_asc(user_token::Column::Id)
-            .paginate(&self.0, per_page);
-        let num_pages = paginator.num_pages().await.map_err(to_db_error)?;
-        // Fetch paginator tokens
-        paginator.fetch_page(page).await.map_err(to_db_error).map(|p| (p, num_pages))
-    }
-    pub async fn get_user_tokens_by_user_id(&self, user_id: u64) -> Result<Vec<user_token::Model>, DatabaseError> {
+    /// Counts enabled tokens of user which haven't expired yet.
+    pub async fn get_active_user_tokens_count(&self, user_id: u64) -> Result<u64, DatabaseError> {
         UserToken::find()
             .filter(user_token::Column::UserId.eq(user_id))
-            .order_by_asc(user_token::Column::Id)
-            .all(&self.0)
+            .filter(user_token::Column::Enabled.eq(true))
+            .filter(Condition::any()
+                .add(user_token::Column::ExpirationTime.is_null())
+                .add(user_token::Column::ExpirationTime.gt(Utc::now().naive_utc())))
+            .count(&self.0)
             .await.map_err(to_db_error)
     }
+    pub async fn get_user_tokens_in_page(&self, user_id: u64, page: PageRequest) -> Result<Page<user_token::Model>, DatabaseError> {
+        let select = UserToken::find()
+            .filter(user_token::Column::UserId.eq(user_id))
+            .order_by_asc(user_token::Column::Id);
+        self.fetch_page(select, user_token::Column::Id, false, page).await
+    }
     pub async fn get_user_token_by_id(&self, id: u64) -> Result<user_token::Model, DatabaseError> {
-        Ok(UserToken::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})?)
+        UserToken::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})
     }
-    pub async fn get_user_token(&self, token: &str) -> Result<user_token::Model, DatabaseError> {
-        Ok(UserToken::find().filter(user_token::Column::Token.contains(token)).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})?)
+    pub async fn get_user_token(&self, token: &str) -> Result<user_token::Model, GetUserTokenError> {
+        UserToken::find().filter(user_token::Column::Token.eq(token)).one(&self.0).await.map_err(to_db_error)?.ok_or_else(|| GetUserTokenError::TokenNotFound { token: token.to_string() })
     }
     pub async fn create_user_token(&self, user_token: user_token::ActiveModel) -> Result<user_token::ActiveModel, DatabaseError> {
         user_token::ActiveModel {
@@ -201,42 +593,33 @@ impl Repository {
         .save(&self.0)
         .await.map_err(to_db_error)
     }
-    pub async fn update_user_token(&self, id: u64, user_token: user_token::ActiveModel) -> Result<user_token::Model, DatabaseError> {
-        let current_user_token: user_token::ActiveModel = UserToken::find_by_id(id as i32)
-            .one(&self.0)
-            .await.map_err(to_db_error)?
-            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})
-            .map(Into::into)?;
+    /// `version` is the version edit was made against, fails if token changed since then.
+    pub async fn update_user_token(&self, id: u64, version: i32, user_token: user_token::ActiveModel) -> Result<user_token::Model, UpdateError> {
         let user_token = user_token.try_into_model().expect("Can't into model");
-        user_token::ActiveModel {
-            id: current_user_token.id,
-            user_id: current_user_token.user_id,
-            token: current_user_token.token,
+        let user_token = user_token::ActiveModel {
             note: Set(user_token.note.to_owned()),                               // Can be updated
             enabled: Set(user_token.enabled.to_owned()),                         // Can be updated
             expiration_time: Set(user_token.expiration_time.to_owned()),         // Can be updated
-            creation_time: current_user_token.creation_time,
             last_edit_time: Set(Some(Local::now().naive_local().to_owned())),
-            last_usage_time: current_user_token.last_usage_time,
-            version: Set(user_token.version.to_owned()),                         // Can be updated
-        }
-        .update(&self.0)
-        .await.map_err(to_db_error)
+            version: Set(version + 1),
+            ..Default::default()
+        };
+        update_versioned(&self.0, user_token, (user_token::Column::Id, id as i32), (user_token::Column::Version, version)).await
     }
     pub async fn delete_user_token(&self, token: &str) -> Result<(), DeleteUserTokenError> {
         let user_token: user_token::ActiveModel = UserToken::find()
-            .filter(user_token::Column::Token.contains(token))
+            .filter(user_token::Column::Token.eq(token))
             .one(&self.0)
             .await.map_err(to_db_error)?
-            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})
+            .ok_or_else(|| DeleteUserTokenError::TokenNotFound { token: token.to_string() })
             .map(Into::into)?;
 
         user_token.delete(&self.0).await.map_err(to_db_error)?;
         Ok(())
     }
-    pub async fn update_last_token_usage_time(&self, name: &str) -> Result<user_token::Model, DatabaseError> {
+    pub async fn update_last_token_usage_time(&self, token: &str) -> Result<user_token::Model, DatabaseError> {
         let mut user_token: user_token::ActiveModel = UserToken::find()
-            .filter(user_token::Column::Token.contains(name))
+            .filter(user_token::Column::Token.eq(token))
             .one(&self.0)
             .await.map_err(to_db_error)?
             .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("UserToken not found"))})
@@ -246,26 +629,703 @@ impl Repository {
 
         user_token.update(&self.0).await.map_err(to_db_error)
     }
+    // Tag
+    /// `name_pattern` may contain `*` wildcards, matched regardless of case.
+    fn filter_tags(name_pattern: Option<&str>) -> Select<Tag> {
+        let select = Tag::find();
+        match name_pattern {
+            Some(pattern) => select.filter(tag::Column::Id.in_subquery(
+                Query::select()
+                    .column(tag_name::Column::TagId)
+                    .from(TagName)
+                    .and_where(lower_tag_name().like(like_pattern(pattern)))
+                    .to_owned()
+            )),
+            None => select,
+        }
+    }
+    pub async fn get_tags_in_page(&self, name_pattern: Option<&str>, page: PageRequest) -> Result<Page<tag::Model>, DatabaseError> {
+        let select = Self::filter_tags(name_pattern).order_by_desc(tag::Column::Id);
+        self.fetch_page(select, tag::Column::Id, true, page).await
+    }
+    pub async fn get_tag_by_name(&self, name: &str) -> Result<tag::Model, GetTagError> {
+        Tag::find()
+            .inner_join(TagName)
+            .filter(lower_tag_name().eq(name.to_lowercase()))
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| GetTagError::TagNotFound { name: name.to_string() })
+    }
+    /// Returns existing tags together with the names they were found by.
+    pub async fn get_tags_by_names(&self, names: &[String]) -> Result<Vec<(tag_name::Model, tag::Model)>, DatabaseError> {
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        let found = TagName::find()
+            .filter(lower_tag_name().is_in(names))
+            .find_also_related(Tag)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().filter_map(|(name, tag)| Some((name, tag?))).collect())
+    }
+    /// Names of every tag, ordered so the first one is the primary name.
+    pub async fn get_tag_names(&self, tag_ids: &[i32]) -> Result<HashMap<i32, Vec<String>>, DatabaseError> {
+        let names = TagName::find()
+            .filter(tag_name::Column::TagId.is_in(tag_ids.to_vec()))
+            .order_by_asc(tag_name::Column::Ord)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        let mut result: HashMap<i32, Vec<String>> = HashMap::new();
+        for name in names {
+            result.entry(name.tag_id).or_default().push(name.name);
+        }
+        Ok(result)
+    }
+    /// Count of posts tagged by every tag.
+    pub async fn get_tag_usages(&self, tag_ids: &[i32]) -> Result<HashMap<i32, i64>, DatabaseError> {
+        let usages: Vec<(i32, i64)> = PostTag::find()
+            .select_only()
+            .column(post_tag::Column::TagId)
+            .column_as(post_tag::Column::PostId.count(), "usages")
+            .filter(post_tag::Column::TagId.is_in(tag_ids.to_vec()))
+            .group_by(post_tag::Column::TagId)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(usages.into_iter().collect())
+    }
+    /// Returns `(post_id, tag)` pairs for given posts.
+    pub async fn get_post_tags(&self, post_ids: &[i32]) -> Result<Vec<(i32, tag::Model)>, DatabaseError> {
+        let found = PostTag::find()
+            .filter(post_tag::Column::PostId.is_in(post_ids.to_vec()))
+            .find_also_related(Tag)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().filter_map(|(post_tag, tag)| Some((post_tag.post_id, tag?))).collect())
+    }
+    pub async fn get_tags_by_ids(&self, ids: &[i32]) -> Result<Vec<tag::Model>, DatabaseError> {
+        Tag::find()
+            .filter(tag::Column::Id.is_in(ids.to_vec()))
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    /// Returns `(parent_id, child_id)` pairs of implications of given tags.
+    pub async fn get_tag_implications(&self, tag_ids: &[i32]) -> Result<Vec<(i32, i32)>, DatabaseError> {
+        let found = TagImplication::find()
+            .filter(tag_implication::Column::ParentId.is_in(tag_ids.to_vec()))
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().map(|implication| (implication.parent_id, implication.child_id)).collect())
+    }
+    /// Returns `(parent_id, child_id)` pairs of suggestions of given tags.
+    pub async fn get_tag_suggestions(&self, tag_ids: &[i32]) -> Result<Vec<(i32, i32)>, DatabaseError> {
+        let found = TagSuggestion::find()
+            .filter(tag_suggestion::Column::ParentId.is_in(tag_ids.to_vec()))
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().map(|suggestion| (suggestion.parent_id, suggestion.child_id)).collect())
+    }
+    async fn insert_tag_names<C: ConnectionTrait>(db: &C, tag_id: i32, names: &[String]) -> Result<(), DbErr> {
+        TagName::insert_many(names.iter().enumerate().map(|(ord, name)| tag_name::ActiveModel {
+            tag_id: Set(tag_id),
+            name: Set(name.to_owned()),
+            ord: Set(ord as i32),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+    async fn insert_tag<C: ConnectionTrait>(db: &C, names: &[String], tag: tag::ActiveModel) -> Result<tag::Model, DbErr> {
+        let tag = tag::ActiveModel {
+            creation_time: Set(Local::now().naive_local().to_owned()),
+            last_edit_time: Set(None),
+            version: Set(1),
+            ..tag
+        }
+        .insert(db)
+        .await?;
+        Self::insert_tag_names(db, tag.id, names).await?;
+        Ok(tag)
+    }
+    pub async fn create_tag(&self, names: &[String], tag: tag::ActiveModel, related: TagRelated) -> Result<tag::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let tag = Self::insert_tag(&txn, names, tag).await.map_err(to_db_error)?;
+        Self::replace_tag_related(&txn, tag.id, related).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(tag)
+    }
+    /// Updates tag, its names are replaced when `names` is given.
+    /// `version` is the version edit was made against, fails if tag changed since then.
+    pub async fn update_tag(&self, id: u64, version: i32, tag: tag::ActiveModel, names: Option<&[String]>, related: TagRelated) -> Result<tag::Model, UpdateError> {
+        let tag = tag.try_into_model().expect("Can't into model");
+        let tag = tag::ActiveModel {
+            category: Set(tag.category.to_owned()),         // Can be updated
+            description: Set(tag.description.to_owned()),   // Can be updated
+            last_edit_time: Set(Some(Local::now().naive_local().to_owned())),
+            version: Set(version + 1),
+            ..Default::default()
+        };
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let tag = update_versioned(&txn, tag, (tag::Column::Id, id as i32), (tag::Column::Version, version)).await?;
+        if let Some(names) = names {
+            TagName::delete_many()
+                .filter(tag_name::Column::TagId.eq(id as i32))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+            Self::insert_tag_names(&txn, id as i32, names).await.map_err(to_db_error)?;
+        }
+        Self::replace_tag_related(&txn, tag.id, related).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(tag)
+    }
+    pub async fn delete_tag(&self, id: u64) -> Result<(), DatabaseError> {
+        let tag: tag::ActiveModel = Tag::find_by_id(id as i32)
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Tag not found"))})
+            .map(Into::into)?;
+
+        tag.delete(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
+    /// Ids of planned tags, new ones are created. A name planned twice
+    /// within one transaction, e.g. as implication and suggestion, is created once.
+    async fn resolve_planned_tags<C: ConnectionTrait>(db: &C, tags: PlannedTags) -> Result<Vec<i32>, DbErr> {
+        let mut tag_ids = tags.tag_ids;
+        for name in tags.new_names.iter() {
+            let existing = TagName::find()
+                .filter(lower_tag_name().eq(name.to_lowercase()))
+                .one(db)
+                .await?;
+            let tag_id = match existing {
+                Some(existing) => existing.tag_id,
+                None => {
+                    let form_data = tag::ActiveModel {
+                        category: Set(tags.category.clone()),
+                        ..Default::default()
+                    };
+                    Self::insert_tag(db, std::slice::from_ref(name), form_data).await?.id
+                }
+            };
+            if !tag_ids.contains(&tag_id) {
+                tag_ids.push(tag_id);
+            }
+        }
+        Ok(tag_ids)
+    }
+    /// Creates tags named in `tags.new_names`, then replaces every tag of post.
+    async fn replace_post_tags<C: ConnectionTrait>(db: &C, post_id: i32, tags: PlannedTags) -> Result<(), DbErr> {
+        let tag_ids = Self::resolve_planned_tags(db, tags).await?;
+        PostTag::delete_many()
+            .filter(post_tag::Column::PostId.eq(post_id))
+            .exec(db)
+            .await?;
+        if !tag_ids.is_empty() {
+            PostTag::insert_many(tag_ids.into_iter().map(|tag_id| post_tag::ActiveModel {
+                post_id: Set(post_id),
+                tag_id: Set(tag_id),
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+    async fn replace_tag_related<C: ConnectionTrait>(db: &C, tag_id: i32, related: TagRelated) -> Result<(), DbErr> {
+        if let Some(implications) = related.implications {
+            let child_ids = Self::resolve_planned_tags(db, implications).await?;
+            Self::replace_tag_implications(db, tag_id, &child_ids).await?;
+        }
+        if let Some(suggestions) = related.suggestions {
+            let child_ids = Self::resolve_planned_tags(db, suggestions).await?;
+            Self::replace_tag_suggestions(db, tag_id, &child_ids).await?;
+        }
+        Ok(())
+    }
+    /// Replaces every implication of tag.
+    async fn replace_tag_implications<C: ConnectionTrait>(db: &C, tag_id: i32, child_ids: &[i32]) -> Result<(), DbErr> {
+        TagImplication::delete_many()
+            .filter(tag_implication::Column::ParentId.eq(tag_id))
+            .exec(db)
+            .await?;
+        if !child_ids.is_empty() {
+            TagImplication::insert_many(child_ids.iter().map(|child_id| tag_implication::ActiveModel {
+                parent_id: Set(tag_id),
+                child_id: Set(*child_id),
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+    /// Replaces every suggestion of tag.
+    async fn replace_tag_suggestions<C: ConnectionTrait>(db: &C, tag_id: i32, child_ids: &[i32]) -> Result<(), DbErr> {
+        TagSuggestion::delete_many()
+            .filter(tag_suggestion::Column::ParentId.eq(tag_id))
+            .exec(db)
+            .await?;
+        if !child_ids.is_empty() {
+            TagSuggestion::insert_many(child_ids.iter().map(|child_id| tag_suggestion::ActiveModel {
+                parent_id: Set(tag_id),
+                child_id: Set(*child_id),
+            }))
+            .exec(db)
+            .await?;
+        }
+        Ok(())
+    }
+    /// Folds `source` tag into `target` and deletes it. Posts always move,
+    /// names and relations only when asked.
+    /// `snapshot` is written in the same transaction as the merge.
+    pub async fn merge_tags(&self, source_id: i32, target_id: i32, names: bool, implications: bool, suggestions: bool, snapshot: snapshot::ActiveModel) -> Result<tag::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        // Posts
+        let tagged: Vec<post_tag::Model> = PostTag::find()
+            .filter(post_tag::Column::TagId.is_in([source_id, target_id]))
+            .all(&txn)
+            .await.map_err(to_db_error)?;
+        let moved: Vec<post_tag::ActiveModel> = tagged.iter()
+            .filter(|post_tag| post_tag.tag_id == source_id)
+            .filter(|source| !tagged.iter().any(|post_tag| post_tag.tag_id == target_id && post_tag.post_id == source.post_id))
+            .map(|source| post_tag::ActiveModel {
+                post_id: Set(source.post_id),
+                tag_id: Set(target_id),
+            })
+            .collect();
+        if !moved.is_empty() {
+            PostTag::insert_many(moved).exec(&txn).await.map_err(to_db_error)?;
+        }
+        // Names
+        if names {
+            let count = TagName::find()
+                .filter(tag_name::Column::TagId.eq(target_id))
+                .count(&txn)
+                .await.map_err(to_db_error)?;
+            TagName::update_many()
+                .col_expr(tag_name::Column::TagId, Expr::value(target_id))
+                .col_expr(tag_name::Column::Ord, Expr::col(tag_name::Column::Ord).add(count as i32))
+                .filter(tag_name::Column::TagId.eq(source_id))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+        }
+        // Implications
+        if implications {
+            let pairs: Vec<(i32, i32)> = TagImplication::find()
+                .filter(Condition::any()
+                    .add(tag_implication::Column::ParentId.is_in([source_id, target_id]))
+                    .add(tag_implication::Column::ChildId.is_in([source_id, target_id])))
+                .all(&txn)
+                .await.map_err(to_db_error)?
+                .into_iter()
+                .map(|implication| (implication.parent_id, implication.child_id))
+                .collect();
+            let moved = repoint_tag_pairs(&pairs, source_id, target_id);
+            if !moved.is_empty() {
+                TagImplication::insert_many(moved.into_iter().map(|(parent_id, child_id)| tag_implication::ActiveModel {
+                    parent_id: Set(parent_id),
+                    child_id: Set(child_id),
+                }))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+            }
+        }
+        // Suggestions
+        if suggestions {
+            let pairs: Vec<(i32, i32)> = TagSuggestion::find()
+                .filter(Condition::any()
+                    .add(tag_suggestion::Column::ParentId.is_in([source_id, target_id]))
+                    .add(tag_suggestion::Column::ChildId.is_in([source_id, target_id])))
+                .all(&txn)
+                .await.map_err(to_db_error)?
+                .into_iter()
+                .map(|suggestion| (suggestion.parent_id, suggestion.child_id))
+                .collect();
+            let moved = repoint_tag_pairs(&pairs, source_id, target_id);
+            if !moved.is_empty() {
+                TagSuggestion::insert_many(moved.into_iter().map(|(parent_id, child_id)| tag_suggestion::ActiveModel {
+                    parent_id: Set(parent_id),
+                    child_id: Set(child_id),
+                }))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+            }
+        }
+        // Everything left on source goes away by FK cascade
+        Tag::delete_by_id(source_id).exec(&txn).await.map_err(to_db_error)?;
+        let mut target: tag::ActiveModel = Tag::find_by_id(target_id)
+            .one(&txn)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Tag not found"))})
+            .map(Into::into)?;
+        target.version = Set(target.version.unwrap() + 1);
+        target.last_edit_time = Set(Some(Local::now().naive_local().to_owned()));
+        let target = target.update(&txn).await.map_err(to_db_error)?;
+        Self::insert_snapshot(&txn, snapshot).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(target)
+    }
+    // Tag Category
+    pub async fn get_tag_categories(&self) -> Result<Vec<tag_category::Model>, DatabaseError> {
+        TagCategory::find()
+            .order_by_asc(tag_category::Column::Order)
+            .order_by_asc(tag_category::Column::Name)
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    pub async fn get_tag_category_by_name(&self, name: &str) -> Result<tag_category::Model, GetTagCategoryError> {
+        TagCategory::find()
+            .filter(Expr::expr(Func::lower(Expr::col(tag_category::Column::Name))).eq(name.to_lowercase()))
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| GetTagCategoryError::TagCategoryNotFound { name: name.to_string() })
+    }
+    pub async fn get_default_tag_category(&self) -> Result<tag_category::Model, DatabaseError> {
+        TagCategory::find()
+            .filter(tag_category::Column::IsDefault.eq(true))
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Default tag category not found"))})
+    }
+    /// Count of tags in every category, by category name.
+    pub async fn get_tag_category_usages(&self) -> Result<HashMap<String, i64>, DatabaseError> {
+        let usages: Vec<(String, i64)> = Tag::find()
+            .select_only()
+            .column(tag::Column::Category)
+            .column_as(tag::Column::Id.count(), "usages")
+            .group_by(tag::Column::Category)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(usages.into_iter().collect())
+    }
+    pub async fn create_tag_category(&self, tag_category: tag_category::ActiveModel) -> Result<tag_category::Model, DatabaseError> {
+        tag_category::ActiveModel {
+            is_default: Set(false),
+            version: Set(1),
+            ..tag_category
+        }
+        .insert(&self.0)
+        .await.map_err(to_db_error)
+    }
+    /// `version` is the version edit was made against, fails if category changed since then.
+    pub async fn update_tag_category(&self, id: u64, version: i32, tag_category: tag_category::ActiveModel) -> Result<tag_category::Model, UpdateError> {
+        let tag_category = tag_category.try_into_model().expect("Can't into model");
+        let tag_category = tag_category::ActiveModel {
+            name: Set(tag_category.name.to_owned()),          // Tags follow by FK cascade
+            color: Set(tag_category.color.to_owned()),        // Can be updated
+            order: Set(tag_category.order.to_owned()),        // Can be updated
+            version: Set(version + 1),
+            ..Default::default()                              // `is_default` only by set_default_tag_category
+        };
+        update_versioned(&self.0, tag_category, (tag_category::Column::Id, id as i32), (tag_category::Column::Version, version)).await
+    }
+    pub async fn set_default_tag_category(&self, id: u64) -> Result<tag_category::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        TagCategory::update_many()
+            .col_expr(tag_category::Column::IsDefault, Expr::value(false))
+            .filter(tag_category::Column::IsDefault.eq(true))
+            .exec(&txn)
+            .await.map_err(to_db_error)?;
+        let mut tag_category: tag_category::ActiveModel = TagCategory::find_by_id(id as i32)
+            .one(&txn)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("TagCategory not found"))})
+            .map(Into::into)?;
+        tag_category.is_default = Set(true);
+        let tag_category = tag_category.update(&txn).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(tag_category)
+    }
+    pub async fn delete_tag_category(&self, id: u64) -> Result<(), DatabaseError> {
+        let tag_category: tag_category::ActiveModel = TagCategory::find_by_id(id as i32)
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("TagCategory not found"))})
+            .map(Into::into)?;
+
+        tag_category.delete(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
+    // Pool
+    /// `name_pattern` may contain `*` wildcards, matched regardless of case.
+    fn filter_pools(name_pattern: Option<&str>) -> Select<Pool> {
+        let select = Pool::find();
+        match name_pattern {
+            Some(pattern) => select.filter(pool::Column::Id.in_subquery(
+                Query::select()
+                    .column(pool_name::Column::PoolId)
+                    .from(PoolName)
+                    .and_where(lower_pool_name().like(like_pattern(pattern)))
+                    .to_owned()
+            )),
+            None => select,
+        }
+    }
+    pub async fn get_pools_in_page(&self, name_pattern: Option<&str>, page: PageRequest) -> Result<Page<pool::Model>, DatabaseError> {
+        let select = Self::filter_pools(name_pattern).order_by_desc(pool::Column::Id);
+        self.fetch_page(select, pool::Column::Id, true, page).await
+    }
+    pub async fn get_pool_by_id(&self, id: u64) -> Result<pool::Model, GetPoolError> {
+        Pool::find_by_id(id as i32).one(&self.0).await.map_err(to_db_error)?.ok_or(GetPoolError::PoolNotFound { id })
+    }
+    /// Returns existing pools together with the names they were found by.
+    pub async fn get_pools_by_names(&self, names: &[String]) -> Result<Vec<(pool_name::Model, pool::Model)>, DatabaseError> {
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        let found = PoolName::find()
+            .filter(lower_pool_name().is_in(names))
+            .find_also_related(Pool)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(found.into_iter().filter_map(|(name, pool)| Some((name, pool?))).collect())
+    }
+    /// Names of every pool, ordered so the first one is the primary name.
+    pub async fn get_pool_names(&self, pool_ids: &[i32]) -> Result<HashMap<i32, Vec<String>>, DatabaseError> {
+        let names = PoolName::find()
+            .filter(pool_name::Column::PoolId.is_in(pool_ids.to_vec()))
+            .order_by_asc(pool_name::Column::Ord)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        let mut result: HashMap<i32, Vec<String>> = HashMap::new();
+        for name in names {
+            result.entry(name.pool_id).or_default().push(name.name);
+        }
+        Ok(result)
+    }
+    /// Post ids of every pool, in pool order.
+    pub async fn get_pool_posts(&self, pool_ids: &[i32]) -> Result<HashMap<i32, Vec<i32>>, DatabaseError> {
+        let posts = PoolPost::find()
+            .filter(pool_post::Column::PoolId.is_in(pool_ids.to_vec()))
+            .order_by_asc(pool_post::Column::Ord)
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        let mut result: HashMap<i32, Vec<i32>> = HashMap::new();
+        for post in posts {
+            result.entry(post.pool_id).or_default().push(post.post_id);
+        }
+        Ok(result)
+    }
+    /// Pools containing post.
+    pub async fn get_post_pools(&self, post_id: i32) -> Result<Vec<pool::Model>, DatabaseError> {
+        Pool::find()
+            .inner_join(PoolPost)
+            .filter(pool_post::Column::PostId.eq(post_id))
+            .order_by_asc(pool::Column::Id)
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    async fn insert_pool_names<C: ConnectionTrait>(db: &C, pool_id: i32, names: &[String]) -> Result<(), DbErr> {
+        PoolName::insert_many(names.iter().enumerate().map(|(ord, name)| pool_name::ActiveModel {
+            pool_id: Set(pool_id),
+            name: Set(name.to_owned()),
+            ord: Set(ord as i32),
+            ..Default::default()
+        }))
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+    /// Posts are stored with their position, so pool keeps the given order.
+    async fn insert_pool_posts<C: ConnectionTrait>(db: &C, pool_id: i32, post_ids: &[i32]) -> Result<(), DbErr> {
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+        PoolPost::insert_many(post_ids.iter().enumerate().map(|(ord, post_id)| pool_post::ActiveModel {
+            pool_id: Set(pool_id),
+            post_id: Set(*post_id),
+            ord: Set(ord as i32),
+        }))
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+    pub async fn create_pool(&self, names: &[String], post_ids: &[i32], pool: pool::ActiveModel) -> Result<pool::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let pool = pool::ActiveModel {
+            creation_time: Set(Local::now().naive_local().to_owned()),
+            last_edit_time: Set(None),
+            version: Set(1),
+            ..pool
+        }
+        .insert(&txn)
+        .await.map_err(to_db_error)?;
+        Self::insert_pool_names(&txn, pool.id, names).await.map_err(to_db_error)?;
+        Self::insert_pool_posts(&txn, pool.id, post_ids).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(pool)
+    }
+    /// Updates pool, its names and posts are replaced when given.
+    /// `version` is the version edit was made against, fails if pool changed since then.
+    pub async fn update_pool(&self, id: u64, version: i32, pool: pool::ActiveModel, names: Option<&[String]>, post_ids: Option<&[i32]>) -> Result<pool::Model, UpdateError> {
+        let pool = pool.try_into_model().expect("Can't into model");
+        let pool = pool::ActiveModel {
+            category: Set(pool.category.to_owned()),
+            description: Set(pool.description.to_owned()),
+            last_edit_time: Set(Some(Local::now().naive_local().to_owned())),
+            version: Set(version + 1),
+            ..Default::default()
+        };
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let pool = update_versioned(&txn, pool, (pool::Column::Id, id as i32), (pool::Column::Version, version)).await?;
+        if let Some(names) = names {
+            PoolName::delete_many()
+                .filter(pool_name::Column::PoolId.eq(id as i32))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+            Self::insert_pool_names(&txn, id as i32, names).await.map_err(to_db_error)?;
+        }
+        if let Some(post_ids) = post_ids {
+            PoolPost::delete_many()
+                .filter(pool_post::Column::PoolId.eq(id as i32))
+                .exec(&txn)
+                .await.map_err(to_db_error)?;
+            Self::insert_pool_posts(&txn, id as i32, post_ids).await.map_err(to_db_error)?;
+        }
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(pool)
+    }
+    pub async fn delete_pool(&self, id: u64) -> Result<(), DatabaseError> {
+        let pool: pool::ActiveModel = Pool::find_by_id(id as i32)
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Pool not found"))})
+            .map(Into::into)?;
+
+        pool.delete(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
+    /// Appends posts of `source` pool to the end of `target` and deletes `source`.
+    /// `snapshot` is written in the same transaction as the merge.
+    pub async fn merge_pools(&self, source_id: i32, target_id: i32, snapshot: snapshot::ActiveModel) -> Result<pool::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        let posts: Vec<pool_post::Model> = PoolPost::find()
+            .filter(pool_post::Column::PoolId.is_in([source_id, target_id]))
+            .order_by_asc(pool_post::Column::Ord)
+            .all(&txn)
+            .await.map_err(to_db_error)?;
+        let (source_posts, target_posts): (Vec<_>, Vec<_>) = posts.into_iter().partition(|post| post.pool_id == source_id);
+        let next_ord = target_posts.iter().map(|post| post.ord + 1).max().unwrap_or_default();
+        let moved: Vec<pool_post::ActiveModel> = source_posts.iter()
+            .filter(|source| !target_posts.iter().any(|post| post.post_id == source.post_id))
+            .enumerate()
+            .map(|(ord, source)| pool_post::ActiveModel {
+                pool_id: Set(target_id),
+                post_id: Set(source.post_id),
+                ord: Set(next_ord + ord as i32),
+            })
+            .collect();
+        if !moved.is_empty() {
+            PoolPost::insert_many(moved).exec(&txn).await.map_err(to_db_error)?;
+        }
+        // Names and posts left on source go away by FK cascade
+        Pool::delete_by_id(source_id).exec(&txn).await.map_err(to_db_error)?;
+        let mut target: pool::ActiveModel = Pool::find_by_id(target_id)
+            .one(&txn)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Pool not found"))})
+            .map(Into::into)?;
+        target.version = Set(target.version.unwrap() + 1);
+        target.last_edit_time = Set(Some(Local::now().naive_local().to_owned()));
+        let target = target.update(&txn).await.map_err(to_db_error)?;
+        Self::insert_snapshot(&txn, snapshot).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(target)
+    }
+    // Pool Category
+    pub async fn get_pool_categories(&self) -> Result<Vec<pool_category::Model>, DatabaseError> {
+        PoolCategory::find()
+            .order_by_asc(pool_category::Column::Name)
+            .all(&self.0)
+            .await.map_err(to_db_error)
+    }
+    pub async fn get_pool_category_by_name(&self, name: &str) -> Result<pool_category::Model, GetPoolCategoryError> {
+        PoolCategory::find()
+            .filter(Expr::expr(Func::lower(Expr::col(pool_category::Column::Name))).eq(name.to_lowercase()))
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| GetPoolCategoryError::PoolCategoryNotFound { name: name.to_string() })
+    }
+    pub async fn get_default_pool_category(&self) -> Result<pool_category::Model, DatabaseError> {
+        PoolCategory::find()
+            .filter(pool_category::Column::IsDefault.eq(true))
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("Default pool category not found"))})
+    }
+    /// Count of pools in every category, by category name.
+    pub async fn get_pool_category_usages(&self) -> Result<HashMap<String, i64>, DatabaseError> {
+        let usages: Vec<(String, i64)> = Pool::find()
+            .select_only()
+            .column(pool::Column::Category)
+            .column_as(pool::Column::Id.count(), "usages")
+            .group_by(pool::Column::Category)
+            .into_tuple()
+            .all(&self.0)
+            .await.map_err(to_db_error)?;
+        Ok(usages.into_iter().collect())
+    }
+    pub async fn create_pool_category(&self, pool_category: pool_category::ActiveModel) -> Result<pool_category::Model, DatabaseError> {
+        pool_category::ActiveModel {
+            is_default: Set(false),
+            version: Set(1),
+            ..pool_category
+        }
+        .insert(&self.0)
+        .await.map_err(to_db_error)
+    }
+    /// `version` is the version edit was made against, fails if category changed since then.
+    pub async fn update_pool_category(&self, id: u64, version: i32, pool_category: pool_category::ActiveModel) -> Result<pool_category::Model, UpdateError> {
+        let pool_category = pool_category.try_into_model().expect("Can't into model");
+        let pool_category = pool_category::ActiveModel {
+            name: Set(pool_category.name.to_owned()),         // Pools follow by FK cascade
+            color: Set(pool_category.color.to_owned()),       // Can be updated
+            version: Set(version + 1),
+            ..Default::default()                              // `is_default` only by set_default_pool_category
+        };
+        update_versioned(&self.0, pool_category, (pool_category::Column::Id, id as i32), (pool_category::Column::Version, version)).await
+    }
+    pub async fn set_default_pool_category(&self, id: u64) -> Result<pool_category::Model, DatabaseError> {
+        let txn = self.0.begin().await.map_err(to_db_error)?;
+        PoolCategory::update_many()
+            .col_expr(pool_category::Column::IsDefault, Expr::value(false))
+            .filter(pool_category::Column::IsDefault.eq(true))
+            .exec(&txn)
+            .await.map_err(to_db_error)?;
+        let mut pool_category: pool_category::ActiveModel = PoolCategory::find_by_id(id as i32)
+            .one(&txn)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("PoolCategory not found"))})
+            .map(Into::into)?;
+        pool_category.is_default = Set(true);
+        let pool_category = pool_category.update(&txn).await.map_err(to_db_error)?;
+        txn.commit().await.map_err(to_db_error)?;
+        Ok(pool_category)
+    }
+    pub async fn delete_pool_category(&self, id: u64) -> Result<(), DatabaseError> {
+        let pool_category: pool_category::ActiveModel = PoolCategory::find_by_id(id as i32)
+            .one(&self.0)
+            .await.map_err(to_db_error)?
+            .ok_or_else(|| {DatabaseError::from(anyhow::anyhow!("PoolCategory not found"))})
+            .map(Into::into)?;
+
+        pool_category.delete(&self.0).await.map_err(to_db_error)?;
+        Ok(())
+    }
     // Snapshot
     pub async fn get_snapshots_count(&self) -> Result<u64, DatabaseError> {
         Snapshot::find().count(&self.0).await.map_err(to_db_error)
     }
-    pub async fn get_snapshots_in_page(&self, page: u64, per_page: u64) -> Result<(Vec<snapshot::Model>, u64), DatabaseError> {
-        // Setup paginator
-        let paginator = Snapshot::find()
-            .order_by_asc(snapshot::Column::Id)
-            .paginate(&self.0, per_page);
-        let num_pages = paginator.num_pages().await.map_err(to_db_error)?;
-        // Fetch paginator users
-        paginator.fetch_page(page - 1).await.map_err(to_db_error).map(|p| (p, num_pages))
+    pub async fn get_snapshots_in_page(&self, page: PageRequest) -> Result<Page<snapshot::Model>, DatabaseError> {
+        let select = Snapshot::find().order_by_asc(snapshot::Column::Id);
+        self.fetch_page(select, snapshot::Column::Id, false, page).await
     }
-    pub async fn create_snapshot(&self, snapshot: snapshot::ActiveModel) -> Result<snapshot::ActiveModel, DatabaseError> {
+    async fn insert_snapshot<C: ConnectionTrait>(db: &C, snapshot: snapshot::ActiveModel) -> Result<snapshot::ActiveModel, DbErr> {
         snapshot::ActiveModel {
             creation_time: Set(Local::now().naive_local().to_owned()),
             ..snapshot
         }
-        .save(&self.0)
-        .await.map_err(to_db_error)
+        .save(db)
+        .await
+    }
+    pub async fn create_snapshot(&self, snapshot: snapshot::ActiveModel) -> Result<snapshot::ActiveModel, DatabaseError> {
+        Self::insert_snapshot(&self.0, snapshot).await.map_err(to_db_error)
     }
     pub async fn delete_snapshot(&self, id: u64) -> Result<(), DatabaseError> {
         let snapshot: snapshot::ActiveModel = Snapshot::find_by_id(id as i32)
@@ -277,4 +1337,18 @@ impl Repository {
         snapshot.delete(&self.0).await.map_err(to_db_error)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repoint_pairs() {
+        // 1 is merged into 2
+        let pairs = [(1, 3), (4, 1), (1, 2), (2, 3), (5, 6)];
+        assert_eq!(repoint_tag_pairs(&pairs, 1, 2), vec![(4, 2)]);
+        assert_eq!(repoint_tag_pairs(&[(1, 3), (3, 1)], 1, 2), vec![(2, 3), (3, 2)]);
+        assert_eq!(repoint_tag_pairs(&[(1, 2), (2, 1)], 1, 2), vec![]);
+        assert_eq!(repoint_tag_pairs(&[(5, 6)], 1, 2), vec![]);
+    }
+}
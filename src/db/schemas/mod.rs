@@ -3,7 +3,23 @@
 pub mod prelude;
 pub mod prelude_model;
 
+pub mod comment;
+pub mod comment_score;
+pub mod pool;
+pub mod pool_category;
+pub mod pool_name;
+pub mod pool_post;
 pub mod post;
+pub mod post_favorite;
+pub mod post_note;
+pub mod post_relation;
+pub mod post_score;
+pub mod post_tag;
 pub mod snapshot;
+pub mod tag;
+pub mod tag_category;
+pub mod tag_implication;
+pub mod tag_name;
+pub mod tag_suggestion;
 pub mod user;
 pub mod user_token;
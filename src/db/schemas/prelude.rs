@@ -1,6 +1,22 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+pub use super::comment::Entity as Comment;
+pub use super::comment_score::Entity as CommentScore;
+pub use super::pool::Entity as Pool;
+pub use super::pool_category::Entity as PoolCategory;
+pub use super::pool_name::Entity as PoolName;
+pub use super::pool_post::Entity as PoolPost;
 pub use super::post::Entity as Post;
+pub use super::post_favorite::Entity as PostFavorite;
+pub use super::post_note::Entity as PostNote;
+pub use super::post_relation::Entity as PostRelation;
+pub use super::post_score::Entity as PostScore;
+pub use super::post_tag::Entity as PostTag;
 pub use super::snapshot::Entity as Snapshot;
+pub use super::tag::Entity as Tag;
+pub use super::tag_category::Entity as TagCategory;
+pub use super::tag_implication::Entity as TagImplication;
+pub use super::tag_name::Entity as TagName;
+pub use super::tag_suggestion::Entity as TagSuggestion;
 pub use super::user::Entity as User;
 pub use super::user_token::Entity as UserToken;
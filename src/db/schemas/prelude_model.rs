@@ -1,4 +1,20 @@
+pub use super::comment::Model as Comment;
+pub use super::comment_score::Model as CommentScore;
+pub use super::pool::Model as Pool;
+pub use super::pool_category::Model as PoolCategory;
+pub use super::pool_name::Model as PoolName;
+pub use super::pool_post::Model as PoolPost;
 pub use super::post::Model as Post;
+pub use super::post_favorite::Model as PostFavorite;
+pub use super::post_note::Model as PostNote;
+pub use super::post_relation::Model as PostRelation;
+pub use super::post_score::Model as PostScore;
+pub use super::post_tag::Model as PostTag;
 pub use super::snapshot::Model as Snapshot;
+pub use super::tag::Model as Tag;
+pub use super::tag_category::Model as TagCategory;
+pub use super::tag_implication::Model as TagImplication;
+pub use super::tag_name::Model as TagName;
+pub use super::tag_suggestion::Model as TagSuggestion;
 pub use super::user::Model as User;
 pub use super::user_token::Model as UserToken;
\ No newline at end of file
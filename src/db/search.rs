@@ -0,0 +1,223 @@
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, Func, LikeExpr, NullOrdering, Query, SimpleExpr};
+
+use crate::db::schemas::{prelude::*, comment, post, post_favorite, post_score, post_tag, tag_name, user};
+use crate::func::search::{
+    CommentFilter, CommentSearch, CommentSort, Criterion, DateInterval, PostFilter, PostSearch, PostSort, Special
+};
+
+/// `*` wildcard to SQL `LIKE` pattern, other special characters are escaped.
+pub fn like_pattern(pattern: &str) -> LikeExpr {
+    let escaped = pattern.to_lowercase().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    LikeExpr::new(escaped.replace('*', "%")).escape('\\')
+}
+
+fn criterion_condition<T: Into<Value> + Clone>(expr: SimpleExpr, criterion: &Criterion<T>) -> Condition {
+    match criterion {
+        Criterion::OneOf(values) => Condition::all().add(Expr::expr(expr).is_in(values.clone())),
+        Criterion::Range(min, max) => Condition::all()
+            .add_option(min.clone().map(|min| Expr::expr(expr.clone()).gte(min)))
+            .add_option(max.clone().map(|max| Expr::expr(expr.clone()).lte(max))),
+    }
+}
+
+fn tag_count() -> SimpleExpr {
+    SimpleExpr::SubQuery(None, Box::new(
+        Query::select()
+            .expr(Expr::col((PostTag, post_tag::Column::TagId)).count())
+            .from(PostTag)
+            .and_where(Expr::col((PostTag, post_tag::Column::PostId)).equals((Post, post::Column::Id)))
+            .to_owned()
+            .into_sub_query_statement()
+    ))
+}
+
+/// Sum of post scores, zero for posts without them.
+fn score() -> SimpleExpr {
+    Func::coalesce([
+        SimpleExpr::SubQuery(None, Box::new(
+            Query::select()
+                .expr(Expr::col((PostScore, post_score::Column::Score)).sum())
+                .from(PostScore)
+                .and_where(Expr::col((PostScore, post_score::Column::PostId)).equals((Post, post::Column::Id)))
+                .to_owned()
+                .into_sub_query_statement()
+        )),
+        Expr::val(0).into(),
+    ]).into()
+}
+
+fn comment_count() -> SimpleExpr {
+    SimpleExpr::SubQuery(None, Box::new(
+        Query::select()
+            .expr(Expr::col((Comment, comment::Column::Id)).count())
+            .from(Comment)
+            .and_where(Expr::col((Comment, comment::Column::PostId)).equals((Post, post::Column::Id)))
+            .to_owned()
+            .into_sub_query_statement()
+    ))
+}
+
+fn dates_condition(column: impl ColumnTrait, intervals: &[DateInterval]) -> Condition {
+    intervals.iter().fold(Condition::any(), |condition, (start, end)| {
+        condition.add(Condition::all()
+            .add_option(start.map(|start| column.gte(start)))
+            .add_option(end.map(|end| column.lt(end))))
+    })
+}
+
+/// Special tokens relative to user match nothing for anonymous users.
+fn special_condition(special: Special, user_id: Option<i32>) -> Condition {
+    let own_scores = |user_id: i32, score: i32| Query::select()
+        .column(post_score::Column::PostId)
+        .from(PostScore)
+        .and_where(post_score::Column::UserId.eq(user_id))
+        .and_where(post_score::Column::Score.eq(score))
+        .to_owned();
+    let posts = match (special, user_id) {
+        (Special::Tumbleweed, _) => {
+            return Condition::all()
+                .add(Expr::expr(score()).eq(0))
+                .add(Expr::expr(comment_count()).eq(0))
+                .add(post::Column::Id.not_in_subquery(Query::select().column(post_favorite::Column::PostId).from(PostFavorite).to_owned()));
+        }
+        (_, None) => return Condition::any(),
+        (Special::Fav, Some(user_id)) => Query::select()
+            .column(post_favorite::Column::PostId)
+            .from(PostFavorite)
+            .and_where(post_favorite::Column::UserId.eq(user_id))
+            .to_owned(),
+        (Special::Liked, Some(user_id)) => own_scores(user_id, 1),
+        (Special::Disliked, Some(user_id)) => own_scores(user_id, -1),
+    };
+    Condition::all().add(post::Column::Id.in_subquery(posts))
+}
+
+fn filter_condition(filter: &PostFilter, user_id: Option<i32>) -> Condition {
+    match filter {
+        PostFilter::Tag(pattern) => Condition::all().add(post::Column::Id.in_subquery(
+            Query::select()
+                .column((PostTag, post_tag::Column::PostId))
+                .from(PostTag)
+                .inner_join(TagName, Expr::col((TagName, tag_name::Column::TagId)).equals((PostTag, post_tag::Column::TagId)))
+                .and_where(Expr::expr(Func::lower(Expr::col((TagName, tag_name::Column::Name)))).like(like_pattern(pattern)))
+                .to_owned()
+        )),
+        PostFilter::Id(criterion) => criterion_condition(Expr::col((Post, post::Column::Id)).into(), criterion),
+        PostFilter::Safety(values) => Condition::all().add(post::Column::Safety.is_in(values.clone())),
+        PostFilter::Type(values) => Condition::all().add(post::Column::Type.is_in(values.clone())),
+        PostFilter::Uploader(patterns) => {
+            let names = patterns.iter().fold(Condition::any(), |condition, pattern| {
+                condition.add(Expr::expr(Func::lower(Expr::col((User, user::Column::Name)))).like(like_pattern(pattern)))
+            });
+            Condition::all().add(post::Column::UserId.in_subquery(
+                Query::select()
+                    .column(user::Column::Id)
+                    .from(User)
+                    .cond_where(names)
+                    .to_owned()
+            ))
+        }
+        PostFilter::TagCount(criterion) => criterion_condition(tag_count(), criterion),
+        PostFilter::Date(intervals) => dates_condition(post::Column::CreationTime, intervals),
+        PostFilter::Score(criterion) => criterion_condition(score(), criterion),
+        PostFilter::CommentCount(criterion) => criterion_condition(comment_count(), criterion),
+        PostFilter::Special(special) => special_condition(*special, user_id),
+    }
+}
+
+/// Posts matching every search term, without ordering.
+/// `user_id` is user who searches.
+pub fn filter_posts(search: &PostSearch, user_id: Option<i32>) -> Select<Post> {
+    search.terms.iter().fold(Post::find(), |select, term| {
+        let condition = filter_condition(&term.filter, user_id);
+        select.filter(if term.negated { condition.not() } else { condition })
+    })
+}
+
+fn order_nulls_last(mut select: Select<Post>, expr: SimpleExpr, order: Order) -> Select<Post> {
+    QueryTrait::query(&mut select).order_by_expr_with_nulls(expr, order, NullOrdering::Last);
+    select
+}
+
+pub fn order_posts(select: Select<Post>, search: &PostSearch) -> Select<Post> {
+    let order = if search.ascending { Order::Asc } else { Order::Desc };
+    let select = match search.sort {
+        PostSort::Id => select,
+        PostSort::Random => return select.order_by(Expr::cust("RANDOM()"), Order::Asc),
+        PostSort::CreationTime => select.order_by(post::Column::CreationTime, order.clone()),
+        PostSort::LastEditTime => order_nulls_last(select, post::Column::LastEditTime.into_simple_expr(), order.clone()),
+        PostSort::TagCount => select.order_by(tag_count(), order.clone()),
+        PostSort::FileSize => order_nulls_last(select, post::Column::FileSize.into_simple_expr(), order.clone()),
+        PostSort::Width => order_nulls_last(select, post::Column::ImageWidth.into_simple_expr(), order.clone()),
+        PostSort::Height => order_nulls_last(select, post::Column::ImageHeight.into_simple_expr(), order.clone()),
+        PostSort::Area => order_nulls_last(
+            select,
+            Expr::col(post::Column::ImageWidth).mul(Expr::col(post::Column::ImageHeight)),
+            order.clone(),
+        ),
+        PostSort::Score => select.order_by(score(), order.clone()),
+        PostSort::CommentCount => select.order_by(comment_count(), order.clone()),
+    };
+    // Stable order for equal values
+    select.order_by(post::Column::Id, order)
+}
+
+fn comment_filter_condition(filter: &CommentFilter) -> Condition {
+    match filter {
+        CommentFilter::Id(criterion) => criterion_condition(Expr::col((Comment, comment::Column::Id)).into(), criterion),
+        CommentFilter::Post(criterion) => criterion_condition(Expr::col((Comment, comment::Column::PostId)).into(), criterion),
+        CommentFilter::User(patterns) => {
+            let names = patterns.iter().fold(Condition::any(), |condition, pattern| {
+                condition.add(Expr::expr(Func::lower(Expr::col((User, user::Column::Name)))).like(like_pattern(pattern)))
+            });
+            Condition::all().add(comment::Column::UserId.in_subquery(
+                Query::select()
+                    .column(user::Column::Id)
+                    .from(User)
+                    .cond_where(names)
+                    .to_owned()
+            ))
+        }
+        CommentFilter::Text(pattern) => Condition::all()
+            .add(Expr::expr(Func::lower(Expr::col((Comment, comment::Column::Text)))).like(like_pattern(pattern))),
+        CommentFilter::CreationDate(intervals) => dates_condition(comment::Column::CreationTime, intervals),
+        CommentFilter::LastEditDate(intervals) => dates_condition(comment::Column::LastEditTime, intervals),
+    }
+}
+
+/// Comments matching every search term, without ordering.
+pub fn filter_comments(search: &CommentSearch) -> Select<Comment> {
+    search.terms.iter().fold(Comment::find(), |select, term| {
+        let condition = comment_filter_condition(&term.filter);
+        select.filter(if term.negated { condition.not() } else { condition })
+    })
+}
+
+pub fn order_comments(mut select: Select<Comment>, search: &CommentSearch) -> Select<Comment> {
+    let order = if search.ascending { Order::Asc } else { Order::Desc };
+    let select = match search.sort {
+        CommentSort::Id => select,
+        CommentSort::Random => return select.order_by(Expr::cust("RANDOM()"), Order::Asc),
+        CommentSort::User => {
+            let name = SimpleExpr::SubQuery(None, Box::new(
+                Query::select()
+                    .column(user::Column::Name)
+                    .from(User)
+                    .and_where(Expr::col((User, user::Column::Id)).equals((Comment, comment::Column::UserId)))
+                    .to_owned()
+                    .into_sub_query_statement()
+            ));
+            QueryTrait::query(&mut select).order_by_expr_with_nulls(name, order.clone(), NullOrdering::Last);
+            select
+        }
+        CommentSort::Post => select.order_by(comment::Column::PostId, order.clone()),
+        CommentSort::CreationTime => select.order_by(comment::Column::CreationTime, order.clone()),
+        CommentSort::LastEditTime => {
+            QueryTrait::query(&mut select).order_by_expr_with_nulls(comment::Column::LastEditTime.into_simple_expr(), order.clone(), NullOrdering::Last);
+            select
+        }
+    };
+    // Stable order for equal values
+    select.order_by(comment::Column::Id, order)
+}
@@ -2,10 +2,13 @@ use axum::{http::StatusCode, response::{IntoResponse, Response}};
 use log::error;
 use serde_json::json;
 
-use crate::db::errors::{DatabaseError, DeleteUserTokenError, GetUserError};
+use crate::db::errors::{DatabaseError, DeleteUserTokenError, GetCommentError, GetPoolCategoryError, GetPoolError, GetPostError, GetTagCategoryError, GetTagError, GetUserError, GetUserTokenError, UpdateError};
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
+/// Errors returned to the client. Names, statuses and titles of responses
+/// follow szurubooru API, so the frontend can branch on `name`.
+#[allow(dead_code)] // Not every error from catalogue is reachable yet
 #[derive(thiserror::Error, Debug)]
 pub enum ApiError {
     #[error(transparent)]
@@ -15,9 +18,138 @@ pub enum ApiError {
     #[error(transparent)]
     GetUser(#[from] GetUserError),
     #[error(transparent)]
+    GetPost(#[from] GetPostError),
+    #[error(transparent)]
+    GetComment(#[from] GetCommentError),
+    #[error(transparent)]
+    GetToken(#[from] GetUserTokenError),
+    #[error(transparent)]
+    GetTag(#[from] GetTagError),
+    #[error(transparent)]
+    GetTagCategory(#[from] GetTagCategoryError),
+    #[error(transparent)]
+    GetPool(#[from] GetPoolError),
+    #[error(transparent)]
+    GetPoolCategory(#[from] GetPoolCategoryError),
+    #[error(transparent)]
     DeleteToken(#[from] DeleteUserTokenError),
+    #[error(transparent)]
+    Update(#[from] UpdateError),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
     #[error("Something went wrong!")]
     Uploads,
+    // Szurubooru errors, each carries its description
+    #[error("{0}")]
+    MissingRequiredFile(String),
+    #[error("{0}")]
+    MissingRequiredParameter(String),
+    #[error("{0}")]
+    InvalidParameter(String),
+    #[error("{0}")]
+    Integrity(String),
+    #[error("{0}")]
+    Search(String),
+    #[error("{0}")]
+    PostNotFound(String),
+    #[error("{0}")]
+    PostAlreadyFeatured(String),
+    #[error("{0}")]
+    PostAlreadyUploaded(String),
+    #[error("{0}")]
+    InvalidPostId(String),
+    #[error("{0}")]
+    InvalidPostSafety(String),
+    #[error("{0}")]
+    InvalidPostSource(String),
+    #[error("{0}")]
+    InvalidPostContent(String),
+    #[error("{0}")]
+    InvalidPostRelation(String),
+    #[error("{0}")]
+    InvalidPostNote(String),
+    #[error("{0}")]
+    InvalidPostFlag(String),
+    #[error("{0}")]
+    InvalidFavoriteTarget(String),
+    #[error("{0}")]
+    InvalidCommentId(String),
+    #[error("{0}")]
+    CommentNotFound(String),
+    #[error("{0}")]
+    EmptyCommentText(String),
+    #[error("{0}")]
+    InvalidScoreTarget(String),
+    #[error("{0}")]
+    InvalidScoreValue(String),
+    #[error("{0}")]
+    TagCategoryNotFound(String),
+    #[error("{0}")]
+    TagCategoryAlreadyExists(String),
+    #[error("{0}")]
+    TagCategoryIsInUse(String),
+    #[error("{0}")]
+    InvalidTagCategoryName(String),
+    #[error("{0}")]
+    InvalidTagCategoryColor(String),
+    #[error("{0}")]
+    TagNotFound(String),
+    #[error("{0}")]
+    TagAlreadyExists(String),
+    #[error("{0}")]
+    TagIsInUse(String),
+    #[error("{0}")]
+    InvalidTagName(String),
+    #[error("{0}")]
+    InvalidTagRelation(String),
+    #[error("{0}")]
+    InvalidTagCategory(String),
+    #[error("{0}")]
+    InvalidTagDescription(String),
+    #[error("{0}")]
+    PoolCategoryAlreadyExists(String),
+    #[error("{0}")]
+    PoolCategoryIsInUse(String),
+    #[error("{0}")]
+    InvalidPoolCategoryName(String),
+    #[error("{0}")]
+    InvalidPoolCategoryColor(String),
+    #[error("{0}")]
+    PoolAlreadyExists(String),
+    #[error("{0}")]
+    InvalidPoolName(String),
+    #[error("{0}")]
+    InvalidPoolDuplicate(String),
+    #[error("{0}")]
+    InvalidPoolCategory(String),
+    #[error("{0}")]
+    InvalidPoolRelation(String),
+    #[error("{0}")]
+    InvalidPoolNonexistentPost(String),
+    #[error("{0}")]
+    UserNotFound(String),
+    #[error("{0}")]
+    UserAlreadyExists(String),
+    #[error("{0}")]
+    InvalidUserName(String),
+    #[error("{0}")]
+    InvalidEmail(String),
+    #[error("{0}")]
+    InvalidPassword(String),
+    #[error("{0}")]
+    InvalidRank(String),
+    #[error("{0}")]
+    InvalidAvatar(String),
+    #[error("{0}")]
+    UserTokenNotFound(String),
+    #[error("{0}")]
+    InvalidUserTokenExpiration(String),
+    #[error("{0}")]
+    Processing(String),
+    #[error("{0}")]
+    Validation(String),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -28,37 +160,178 @@ pub enum TestError {
     SecondEntry, // TODO: И это в том числе!
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        error!("Error on request: {self}");
-        let description = self.to_string();
+#[derive(thiserror::Error, Debug)]
+pub enum AuthError {
+    #[error("Basic and Token authorization must contain base64 encoded 'name:secret' pair.")]
+    MalformedHeader,
+    #[error("Invalid username or password.")]
+    InvalidCredentials,
+    #[error("Invalid token.")]
+    InvalidToken,
+    #[error("Token is disabled.")]
+    DisabledToken,
+    #[error("Token has expired.")]
+    ExpiredToken,
+    #[error("Insufficient privileges to do this.")]
+    InsufficientPrivileges,
+}
+
+/// Szurubooru error classes, they define status code and title of response.
+enum ErrorKind {
+    Validation,
+    Integrity,
+    Search,
+    Auth,
+    NotFound,
+    Processing,
+    Internal,
+}
+
+impl ErrorKind {
+    fn status(&self) -> StatusCode {
         match self {
-            ApiError::Test(TestError::ItsJustForTest) => internal_server_error("InternalError", "ItsJustForTest", &description),
-            ApiError::Test(TestError::SecondEntry) => internal_server_error("InternalError", "SecondEntry", &description),
-            ApiError::Database(_) => internal_server_error("InternalError", &description, &description),
-            ApiError::GetUser(_) => internal_server_error("InternalError", &description, &description),
-            ApiError::DeleteToken(DeleteUserTokenError::DatabaseError(_)) => internal_server_error("InternalError", &description, &description),
-            ApiError::DeleteToken(DeleteUserTokenError::TokenNotFound { .. }) => internal_server_error("InternalError", &description, &description),
-            ApiError::DeleteToken(DeleteUserTokenError::TokenUserIdDontMatch) => method_not_allowed(),
-            ApiError::Uploads => method_not_allowed(),
+            ErrorKind::Validation | ErrorKind::Search | ErrorKind::Processing => StatusCode::BAD_REQUEST,
+            ErrorKind::Integrity => StatusCode::CONFLICT,
+            ErrorKind::Auth => StatusCode::FORBIDDEN,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+    fn title(&self) -> &'static str {
+        match self {
+            ErrorKind::Validation => "Validation error",
+            ErrorKind::Integrity => "Integrity violation",
+            ErrorKind::Search => "Search error",
+            ErrorKind::Auth => "Authentication error",
+            ErrorKind::NotFound => "Not found",
+            ErrorKind::Processing => "Processing error",
+            ErrorKind::Internal => "Internal error",
+        }
+    }
+}
+
+impl ApiError {
+    /// Returns szurubooru error name and its class.
+    fn kind(&self) -> (&'static str, ErrorKind) {
+        use ErrorKind::*;
+        match self {
+            ApiError::Test(_) => ("InternalError", Internal),
+            ApiError::Database(_) => ("InternalError", Internal),
+            ApiError::GetUser(GetUserError::UserNotFound { .. }) => ("UserNotFoundError", NotFound),
+            ApiError::GetUser(GetUserError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetPost(GetPostError::PostNotFound { .. }) => ("PostNotFoundError", NotFound),
+            ApiError::GetPost(GetPostError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetComment(GetCommentError::CommentNotFound { .. }) => ("CommentNotFoundError", NotFound),
+            ApiError::GetComment(GetCommentError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetToken(GetUserTokenError::TokenNotFound { .. }) => ("UserTokenNotFoundError", NotFound),
+            ApiError::GetToken(GetUserTokenError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetTag(GetTagError::TagNotFound { .. }) => ("TagNotFoundError", NotFound),
+            ApiError::GetTag(GetTagError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetTagCategory(GetTagCategoryError::TagCategoryNotFound { .. }) => ("TagCategoryNotFoundError", NotFound),
+            ApiError::GetTagCategory(GetTagCategoryError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetPool(GetPoolError::PoolNotFound { .. }) => ("PoolNotFoundError", NotFound),
+            ApiError::GetPool(GetPoolError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::GetPoolCategory(GetPoolCategoryError::PoolCategoryNotFound { .. }) => ("PoolCategoryNotFoundError", NotFound),
+            ApiError::GetPoolCategory(GetPoolCategoryError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::DeleteToken(DeleteUserTokenError::TokenNotFound { .. }) => ("UserTokenNotFoundError", NotFound),
+            ApiError::DeleteToken(DeleteUserTokenError::TokenUserIdDontMatch) => ("UserTokenNotFoundError", NotFound),
+            ApiError::DeleteToken(DeleteUserTokenError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::Update(UpdateError::VersionMismatch) => ("IntegrityError", Integrity),
+            ApiError::Update(UpdateError::DatabaseError(_)) => ("InternalError", Internal),
+            ApiError::Auth(_) => ("AuthError", Auth),
+            ApiError::Io(_) => ("InternalError", Internal),
+            ApiError::Uploads => ("ProcessingError", Processing),
+            ApiError::MissingRequiredFile(_) => ("MissingRequiredFileError", Validation),
+            ApiError::MissingRequiredParameter(_) => ("MissingRequiredParameterError", Validation),
+            ApiError::InvalidParameter(_) => ("InvalidParameterError", Validation),
+            ApiError::Integrity(_) => ("IntegrityError", Integrity),
+            ApiError::Search(_) => ("SearchError", Search),
+            ApiError::PostNotFound(_) => ("PostNotFoundError", NotFound),
+            ApiError::PostAlreadyFeatured(_) => ("PostAlreadyFeaturedError", Validation),
+            ApiError::PostAlreadyUploaded(_) => ("PostAlreadyUploadedError", Validation),
+            ApiError::InvalidPostId(_) => ("InvalidPostIdError", Validation),
+            ApiError::InvalidPostSafety(_) => ("InvalidPostSafetyError", Validation),
+            ApiError::InvalidPostSource(_) => ("InvalidPostSourceError", Validation),
+            ApiError::InvalidPostContent(_) => ("InvalidPostContentError", Validation),
+            ApiError::InvalidPostRelation(_) => ("InvalidPostRelationError", Validation),
+            ApiError::InvalidPostNote(_) => ("InvalidPostNoteError", Validation),
+            ApiError::InvalidPostFlag(_) => ("InvalidPostFlagError", Validation),
+            ApiError::InvalidFavoriteTarget(_) => ("InvalidFavoriteTargetError", Validation),
+            ApiError::InvalidCommentId(_) => ("InvalidCommentIdError", Validation),
+            ApiError::CommentNotFound(_) => ("CommentNotFoundError", NotFound),
+            ApiError::EmptyCommentText(_) => ("EmptyCommentTextError", Validation),
+            ApiError::InvalidScoreTarget(_) => ("InvalidScoreTargetError", Validation),
+            ApiError::InvalidScoreValue(_) => ("InvalidScoreValueError", Validation),
+            ApiError::TagCategoryNotFound(_) => ("TagCategoryNotFoundError", NotFound),
+            ApiError::TagCategoryAlreadyExists(_) => ("TagCategoryAlreadyExistsError", Validation),
+            ApiError::TagCategoryIsInUse(_) => ("TagCategoryIsInUseError", Validation),
+            ApiError::InvalidTagCategoryName(_) => ("InvalidTagCategoryNameError", Validation),
+            ApiError::InvalidTagCategoryColor(_) => ("InvalidTagCategoryColorError", Validation),
+            ApiError::TagNotFound(_) => ("TagNotFoundError", NotFound),
+            ApiError::TagAlreadyExists(_) => ("TagAlreadyExistsError", Validation),
+            ApiError::TagIsInUse(_) => ("TagIsInUseError", Validation),
+            ApiError::InvalidTagName(_) => ("InvalidTagNameError", Validation),
+            ApiError::InvalidTagRelation(_) => ("InvalidTagRelationError", Validation),
+            ApiError::InvalidTagCategory(_) => ("InvalidTagCategoryError", Validation),
+            ApiError::InvalidTagDescription(_) => ("InvalidTagDescriptionError", Validation),
+            ApiError::PoolCategoryAlreadyExists(_) => ("PoolCategoryAlreadyExistsError", Validation),
+            ApiError::PoolCategoryIsInUse(_) => ("PoolCategoryIsInUseError", Validation),
+            ApiError::InvalidPoolCategoryName(_) => ("InvalidPoolCategoryNameError", Validation),
+            ApiError::InvalidPoolCategoryColor(_) => ("InvalidPoolCategoryColorError", Validation),
+            ApiError::PoolAlreadyExists(_) => ("PoolAlreadyExistsError", Validation),
+            ApiError::InvalidPoolName(_) => ("InvalidPoolNameError", Validation),
+            ApiError::InvalidPoolDuplicate(_) => ("InvalidPoolDuplicateError", Validation),
+            ApiError::InvalidPoolCategory(_) => ("InvalidPoolCategoryError", Validation),
+            ApiError::InvalidPoolRelation(_) => ("InvalidPoolRelationError", Validation),
+            ApiError::InvalidPoolNonexistentPost(_) => ("InvalidPoolNonexistentPostError", Validation),
+            ApiError::UserNotFound(_) => ("UserNotFoundError", NotFound),
+            ApiError::UserAlreadyExists(_) => ("UserAlreadyExistsError", Validation),
+            ApiError::InvalidUserName(_) => ("InvalidUserNameError", Validation),
+            ApiError::InvalidEmail(_) => ("InvalidEmailError", Validation),
+            ApiError::InvalidPassword(_) => ("InvalidPasswordError", Validation),
+            ApiError::InvalidRank(_) => ("InvalidRankError", Validation),
+            ApiError::InvalidAvatar(_) => ("InvalidAvatarError", Validation),
+            ApiError::UserTokenNotFound(_) => ("UserTokenNotFoundError", NotFound),
+            ApiError::InvalidUserTokenExpiration(_) => ("InvalidUserTokenExpirationError", Validation),
+            ApiError::Processing(_) => ("ProcessingError", Processing),
+            ApiError::Validation(_) => ("ValidationError", Validation),
+        }
+    }
+}
+
+/// Optimistic locking: rejects edits made against outdated resource.
+pub fn check_version(current: i32, requested: i32) -> ApiResult<()> {
+    if current != requested {
+        return Err(ApiError::Integrity("Someone else modified this in the meantime. Please try again.".to_string()));
+    }
+    Ok(())
 }
 
-fn internal_server_error(name: &str, title: &str, description: &str) -> Response {
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        [("Content-Type", "application/json")],
-        json!({
-            "name": name,
-            "title": title,
-            "description": description, 
-        }).to_string(),
-    ).into_response()
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        error!("Error on request: {self}");
+        let (name, kind) = self.kind();
+        (
+            kind.status(),
+            [("Content-Type", "application/json")],
+            json!({
+                "name": name,
+                "title": kind.title(),
+                "description": self.to_string(),
+            }).to_string(),
+        ).into_response()
+    }
 }
 
-fn method_not_allowed() -> Response {
-    (
-        StatusCode::METHOD_NOT_ALLOWED,
-    ).into_response()
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(ApiError::UserNotFound(String::new()).into_response().status(), StatusCode::NOT_FOUND);
+        assert_eq!(ApiError::Integrity(String::new()).into_response().status(), StatusCode::CONFLICT);
+        assert_eq!(ApiError::InvalidParameter(String::new()).into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(ApiError::Auth(AuthError::InvalidToken).into_response().status(), StatusCode::FORBIDDEN);
+    }
+}
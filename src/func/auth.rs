@@ -0,0 +1,110 @@
+use std::fmt::Write;
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use ring::{digest, rand::{SecureRandom, SystemRandom}};
+
+use crate::db::schemas::user;
+
+/// Revision of password hashes produced by [`hash_password`].
+/// 1 and 2 are legacy szurubooru hashes (SHA1 and SHA256), 3 is argon2id.
+/// Revisions 2 and 3 hash `secret + salt + password`, like szurubooru does.
+pub const CURRENT_PASSWORD_REVISION: i16 = 3;
+
+const LEGACY_SHA1_SALT: &str = "1A2/$_4xVa";
+
+/// Returns `(password_hash, password_salt)` for the current revision.
+pub fn hash_password(password: &str, secret: &str) -> anyhow::Result<(String, String)> {
+    let mut raw_salt = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut raw_salt)
+        .map_err(|_| anyhow::anyhow!("Can't generate password salt"))?;
+    let salt = SaltString::encode_b64(&raw_salt).map_err(anyhow::Error::msg)?;
+    let hash = Argon2::default()
+        .hash_password(format!("{secret}{salt}{password}").as_bytes(), &salt)
+        .map_err(anyhow::Error::msg)?
+        .to_string();
+    Ok((hash, salt.to_string()))
+}
+
+/// Checks password against stored hash, taking `password_revision` into account.
+pub fn verify_password(user: &user::Model, password: &str, secret: &str) -> bool {
+    let salt = user.password_salt.clone().unwrap_or_default();
+    match user.password_revision {
+        3 => match PasswordHash::new(&user.password_hash) {
+            Ok(hash) => Argon2::default().verify_password(format!("{secret}{salt}{password}").as_bytes(), &hash).is_ok(),
+            Err(_) => false,
+        },
+        2 => legacy_hash(&digest::SHA256, &[secret, &salt, password]) == user.password_hash,
+        1 => legacy_hash(&digest::SHA1_FOR_LEGACY_USE_ONLY, &[LEGACY_SHA1_SALT, &salt, password]) == user.password_hash,
+        _ => false,
+    }
+}
+
+/// Whether the stored hash should be replaced after a successful login.
+pub fn needs_rehash(user: &user::Model) -> bool {
+    user.password_revision < CURRENT_PASSWORD_REVISION
+}
+
+fn legacy_hash(algorithm: &'static digest::Algorithm, parts: &[&str]) -> String {
+    let mut context = digest::Context::new(algorithm);
+    for part in parts {
+        context.update(part.as_bytes());
+    }
+    let mut result = String::new();
+    for byte in context.finish().as_ref() {
+        write!(result, "{:02x}", byte).unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    fn user_with(password_hash: String, password_salt: String, password_revision: i16) -> user::Model {
+        user::Model {
+            id: 1,
+            name: "test".to_string(),
+            password_hash,
+            password_salt: Some(password_salt),
+            email: None,
+            rank: "regular".to_string(),
+            creation_time: Local::now().naive_local(),
+            last_login_time: None,
+            avatar_style: "gravatar".to_string(),
+            version: 1,
+            password_revision,
+        }
+    }
+
+    #[test]
+    fn argon2_roundtrip() {
+        let (hash, salt) = hash_password("hunter2", "secret").unwrap();
+        let user = user_with(hash, salt, CURRENT_PASSWORD_REVISION);
+        assert!(verify_password(&user, "hunter2", "secret"));
+        assert!(!verify_password(&user, "hunter3", "secret"));
+        assert!(!verify_password(&user, "hunter2", "other secret"));
+        assert!(!needs_rehash(&user));
+    }
+
+    #[test]
+    fn szurubooru_argon2() {
+        // Made by szurubooru (PyNaCl) with secret "test-secret" and salt "0123456789abcdef"
+        let hash = "$argon2id$v=19$m=65536,t=2,p=1$8KHqNCKCWjqQ/gs0XvIsKg$bR0DEPtPAxpfA76BxZYsjo/jR3MS5sfP0uvx1hGSKek";
+        let user = user_with(hash.to_string(), "0123456789abcdef".to_string(), 3);
+        assert!(verify_password(&user, "hunter2", "test-secret"));
+        assert!(!verify_password(&user, "hunter2", "secret"));
+    }
+
+    #[test]
+    fn legacy_sha256() {
+        let hash = legacy_hash(&digest::SHA256, &["secret", "salt", "hunter2"]);
+        let user = user_with(hash, "salt".to_string(), 2);
+        assert!(verify_password(&user, "hunter2", "secret"));
+        assert!(!verify_password(&user, "hunter2", "other secret"));
+        assert!(needs_rehash(&user));
+    }
+}
@@ -1 +1,5 @@
-pub mod post;
\ No newline at end of file
+pub mod auth;
+pub mod post;
+pub mod search;
+pub mod thumbnail;
+pub mod user;
\ No newline at end of file
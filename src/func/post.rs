@@ -23,4 +23,79 @@ pub fn get_post_content_path<T: Display>(id: T, hash: String, mime: &str) -> Str
 
 pub fn get_post_thumbnail_path<T: Display>(id: T, hash: String) -> String {
     format!("data/generated-thumbnails/{id}_{hash}.jpg").to_string()
-}
\ No newline at end of file
+}
+/// SHA1 of post content, used to find already uploaded files.
+pub fn get_content_checksum(content: &[u8]) -> String {
+    use std::fmt::Write;
+    let binding = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, content);
+    let mut result = String::new();
+    for byte in binding.as_ref() {
+      write!(result, "{:02x}", byte).unwrap();
+    }
+    result
+}
+
+pub fn get_content_md5(content: &[u8]) -> String {
+    use std::fmt::Write;
+    use md5::Digest;
+    let mut result = String::new();
+    for byte in Md5::digest(content) {
+      write!(result, "{:02x}", byte).unwrap();
+    }
+    result
+}
+
+/// Szurubooru post type for given MIME type.
+pub fn get_post_type(mime: &str) -> &'static str {
+    match mime {
+        "image/gif" => "animation",
+        "application/x-shockwave-flash" => "flash",
+        mime if mime.starts_with("video/") => "video",
+        _ => "image",
+    }
+}
+
+pub const POST_SAFETIES: [&str; 3] = ["safe", "sketchy", "unsafe"];
+pub const POST_FLAGS: [&str; 2] = ["loop", "sound"];
+
+/// Note polygon is a list of `[x, y]` points relative to post size,
+/// so every coordinate lies within 0..1.
+pub fn validate_note(polygon: &[Vec<f64>], text: &str) -> Result<(), String> {
+    if polygon.len() < 3 {
+        return Err("Note polygon must have at least three points.".to_string());
+    }
+    for point in polygon.iter() {
+        if point.len() != 2 {
+            return Err(format!("Note point {point:?} must have exactly two coordinates."));
+        }
+        if point.iter().any(|coordinate| !(0.0..=1.0).contains(coordinate)) {
+            return Err(format!("Note point {point:?} must lie within 0 and 1."));
+        }
+    }
+    if text.trim().is_empty() {
+        return Err("Note text cannot be empty.".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(get_content_checksum(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(get_content_md5(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    #[test]
+    fn note_polygons() {
+        let triangle = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![0.5, 1.0]];
+        assert!(validate_note(&triangle, "text").is_ok());
+        assert!(validate_note(&triangle, " ").is_err());
+        assert!(validate_note(&triangle[..2], "text").is_err());
+        assert!(validate_note(&[vec![0.0, 0.0], vec![1.5, 0.0], vec![0.5, 1.0]], "text").is_err());
+        assert!(validate_note(&[vec![0.0], vec![1.0, 0.0], vec![0.5, 1.0]], "text").is_err());
+        assert!(validate_note(&[vec![f64::NAN, 0.0], vec![1.0, 0.0], vec![0.5, 1.0]], "text").is_err());
+    }
+}
@@ -0,0 +1,405 @@
+use std::str::FromStr;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime};
+
+/// Value of named token: `a`, `a,b,c` or `a..b` with optional ends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Criterion<T> {
+    OneOf(Vec<T>),
+    Range(Option<T>, Option<T>),
+}
+
+/// `[start, end)` interval, open ends are `None`.
+pub type DateInterval = (Option<NaiveDateTime>, Option<NaiveDateTime>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostFilter {
+    /// Tag name, `*` works as wildcard
+    Tag(String),
+    Id(Criterion<i32>),
+    Safety(Vec<String>),
+    Type(Vec<String>),
+    /// User names, `*` works as wildcard
+    Uploader(Vec<String>),
+    TagCount(Criterion<i64>),
+    Date(Vec<DateInterval>),
+    Score(Criterion<i64>),
+    CommentCount(Criterion<i64>),
+    /// Relative to user who searches
+    Special(Special),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Special {
+    /// Favorited posts
+    Fav,
+    /// Posts scored with 1
+    Liked,
+    /// Posts scored with -1
+    Disliked,
+    /// Posts without score, favorites and comments
+    Tumbleweed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostTerm {
+    pub filter: PostFilter,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PostSort {
+    #[default]
+    Id,
+    Random,
+    CreationTime,
+    LastEditTime,
+    TagCount,
+    FileSize,
+    Width,
+    Height,
+    Area,
+    Score,
+    CommentCount,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PostSearch {
+    pub terms: Vec<PostTerm>,
+    pub sort: PostSort,
+    pub ascending: bool,
+}
+
+/// Parses szurubooru search query for posts.
+/// Errors are messages for `SearchError`.
+pub fn parse_post_query(query: &str) -> Result<PostSearch, String> {
+    let mut search = PostSearch::default();
+    for token in query.split_whitespace() {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, token),
+        };
+        let Some((key, value)) = split_named(token) else {
+            search.terms.push(PostTerm { filter: PostFilter::Tag(unescape(token)), negated });
+            continue;
+        };
+        let filter = match key.to_lowercase().as_str() {
+            "sort" => {
+                (search.sort, search.ascending) = parse_sort(&value, negated)?;
+                continue;
+            }
+            "special" => PostFilter::Special(parse_special(&value)?),
+            "id" => PostFilter::Id(parse_criterion(&value)?),
+            "safety" | "rating" => PostFilter::Safety(parse_list(&value, "safety", safety_alias)?),
+            "type" => PostFilter::Type(parse_list(&value, "type", type_alias)?),
+            "uploader" | "upload" | "submit" => PostFilter::Uploader(parse_list(&value, "uploader", |name| Some(name.to_lowercase()))?),
+            "tag-count" => PostFilter::TagCount(parse_criterion(&value)?),
+            "date" | "time" | "creation-date" | "creation-time" => PostFilter::Date(parse_dates(&value)?),
+            "score" => PostFilter::Score(parse_criterion(&value)?),
+            "comment-count" => PostFilter::CommentCount(parse_criterion(&value)?),
+            // Namespaced tags like `character:name` must be escaped as `character\:name`
+            _ => return Err(format!("Unknown named token {key:?}.")),
+        };
+        search.terms.push(PostTerm { filter, negated });
+    }
+    Ok(search)
+}
+
+/// Splits token on the first colon not escaped with `\`.
+fn split_named(token: &str) -> Option<(String, String)> {
+    let mut escaped = false;
+    for (i, c) in token.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            ':' if !escaped => return Some((unescape(&token[..i]), token[i + 1..].to_string())),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut escaped = false;
+    for c in text.chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        result.push(c);
+    }
+    result
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T, String> {
+    unescape(value).parse().map_err(|_| format!("Invalid value {value:?}."))
+}
+
+fn parse_criterion<T: FromStr>(value: &str) -> Result<Criterion<T>, String> {
+    if value.contains(',') {
+        return Ok(Criterion::OneOf(value.split(',').map(parse_value).collect::<Result<_, _>>()?));
+    }
+    if let Some((min, max)) = value.split_once("..") {
+        let min = if min.is_empty() { None } else { Some(parse_value(min)?) };
+        let max = if max.is_empty() { None } else { Some(parse_value(max)?) };
+        return Ok(Criterion::Range(min, max));
+    }
+    Ok(Criterion::OneOf(vec![parse_value(value)?]))
+}
+
+/// Comma separated values, each passed through `alias`.
+fn parse_list(value: &str, key: &str, alias: impl Fn(&str) -> Option<String>) -> Result<Vec<String>, String> {
+    if value.contains("..") {
+        return Err(format!("Ranges are not supported for {key:?}."));
+    }
+    value
+        .split(',')
+        .map(|item| alias(&unescape(item)).ok_or_else(|| format!("Invalid {key} {item:?}.")))
+        .collect()
+}
+
+fn safety_alias(value: &str) -> Option<String> {
+    match value.to_lowercase().as_str() {
+        "safe" => Some("safe"),
+        "sketchy" | "questionable" => Some("sketchy"),
+        "unsafe" => Some("unsafe"),
+        _ => None,
+    }.map(str::to_string)
+}
+
+fn type_alias(value: &str) -> Option<String> {
+    match value.to_lowercase().as_str() {
+        "image" | "img" | "jpg" | "png" => Some("image"),
+        "animation" | "animated" | "anim" | "gif" => Some("animation"),
+        "video" | "webm" | "mp4" => Some("video"),
+        "flash" | "swf" => Some("flash"),
+        _ => None,
+    }.map(str::to_string)
+}
+
+/// Returns `[start, end)` of `YYYY`, `YYYY-MM`, `YYYY-MM-DD`, `today` or `yesterday`.
+fn parse_date(value: &str) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let invalid = || format!("Invalid date {value:?}.");
+    let today = Local::now().date_naive();
+    let (start, end) = match value.to_lowercase().as_str() {
+        "today" => (today, today.checked_add_signed(Duration::days(1)).ok_or_else(invalid)?),
+        "yesterday" => (today.checked_sub_signed(Duration::days(1)).ok_or_else(invalid)?, today),
+        value => {
+            let parts: Vec<u32> = value.split('-').map(|part| part.parse().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+            let year = |year: u32| i32::try_from(year).map_err(|_| invalid());
+            match parts[..] {
+                [y] => {
+                    let start = NaiveDate::from_ymd_opt(year(y)?, 1, 1).ok_or_else(invalid)?;
+                    (start, NaiveDate::from_ymd_opt(start.year() + 1, 1, 1).ok_or_else(invalid)?)
+                }
+                [y, month] => {
+                    let start = NaiveDate::from_ymd_opt(year(y)?, month, 1).ok_or_else(invalid)?;
+                    let end = if month == 12 {
+                        NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                    } else {
+                        NaiveDate::from_ymd_opt(start.year(), month + 1, 1)
+                    };
+                    (start, end.ok_or_else(invalid)?)
+                }
+                [y, month, day] => {
+                    let start = NaiveDate::from_ymd_opt(year(y)?, month, day).ok_or_else(invalid)?;
+                    (start, start.checked_add_signed(Duration::days(1)).ok_or_else(invalid)?)
+                }
+                _ => return Err(invalid()),
+            }
+        }
+    };
+    Ok((start.and_hms_opt(0, 0, 0).unwrap(), end.and_hms_opt(0, 0, 0).unwrap()))
+}
+
+fn parse_dates(value: &str) -> Result<Vec<DateInterval>, String> {
+    Ok(match parse_criterion::<String>(value)? {
+        Criterion::OneOf(dates) => dates
+            .iter()
+            .map(|date| parse_date(date).map(|(start, end)| (Some(start), Some(end))))
+            .collect::<Result<_, _>>()?,
+        Criterion::Range(min, max) => vec![(
+            min.map(|date| parse_date(&date)).transpose()?.map(|(start, _)| start),
+            max.map(|date| parse_date(&date)).transpose()?.map(|(_, end)| end),
+        )],
+    })
+}
+
+/// Returns sort and whether it is ascending. Sorting is descending by default,
+/// `-sort:` or `,asc` reverses it.
+fn parse_sort(value: &str, negated: bool) -> Result<(PostSort, bool), String> {
+    let (name, ascending) = match value.rsplit_once(',') {
+        Some((name, "asc")) => (name, true),
+        Some((name, "desc")) => (name, false),
+        _ => (value, false),
+    };
+    let sort = match name.to_lowercase().as_str() {
+        "id" => PostSort::Id,
+        "random" => PostSort::Random,
+        "date" | "time" | "creation-date" | "creation-time" => PostSort::CreationTime,
+        "edit-date" | "edit-time" | "last-edit-date" | "last-edit-time" => PostSort::LastEditTime,
+        "tag-count" => PostSort::TagCount,
+        "file-size" => PostSort::FileSize,
+        "width" | "image-width" => PostSort::Width,
+        "height" | "image-height" => PostSort::Height,
+        "area" | "image-area" => PostSort::Area,
+        "score" => PostSort::Score,
+        "comment-count" => PostSort::CommentCount,
+        _ => return Err(format!("Unknown sort token {name:?}.")),
+    };
+    Ok((sort, ascending != negated))
+}
+
+fn parse_special(value: &str) -> Result<Special, String> {
+    match value.to_lowercase().as_str() {
+        "fav" => Ok(Special::Fav),
+        "liked" => Ok(Special::Liked),
+        "disliked" => Ok(Special::Disliked),
+        "tumbleweed" => Ok(Special::Tumbleweed),
+        _ => Err(format!("Unknown special token {value:?}.")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommentFilter {
+    Id(Criterion<i32>),
+    Post(Criterion<i32>),
+    /// User names, `*` works as wildcard
+    User(Vec<String>),
+    /// `*` works as wildcard
+    Text(String),
+    CreationDate(Vec<DateInterval>),
+    LastEditDate(Vec<DateInterval>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentTerm {
+    pub filter: CommentFilter,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CommentSort {
+    #[default]
+    Id,
+    Random,
+    User,
+    Post,
+    CreationTime,
+    LastEditTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommentSearch {
+    pub terms: Vec<CommentTerm>,
+    pub sort: CommentSort,
+    pub ascending: bool,
+}
+
+/// Parses szurubooru search query for comments, anonymous tokens match text.
+pub fn parse_comment_query(query: &str) -> Result<CommentSearch, String> {
+    let mut search = CommentSearch::default();
+    for token in query.split_whitespace() {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, token),
+        };
+        let Some((key, value)) = split_named(token) else {
+            search.terms.push(CommentTerm { filter: CommentFilter::Text(unescape(token)), negated });
+            continue;
+        };
+        let filter = match key.to_lowercase().as_str() {
+            "sort" => {
+                (search.sort, search.ascending) = parse_comment_sort(&value, negated)?;
+                continue;
+            }
+            "id" => CommentFilter::Id(parse_criterion(&value)?),
+            "post" => CommentFilter::Post(parse_criterion(&value)?),
+            "user" | "author" => CommentFilter::User(parse_list(&value, "user", |name| Some(name.to_lowercase()))?),
+            "text" => CommentFilter::Text(unescape(&value)),
+            "date" | "time" | "creation-date" | "creation-time" => CommentFilter::CreationDate(parse_dates(&value)?),
+            "edit-date" | "edit-time" | "last-edit-date" | "last-edit-time" => CommentFilter::LastEditDate(parse_dates(&value)?),
+            _ => return Err(format!("Unknown named token {key:?}.")),
+        };
+        search.terms.push(CommentTerm { filter, negated });
+    }
+    Ok(search)
+}
+
+fn parse_comment_sort(value: &str, negated: bool) -> Result<(CommentSort, bool), String> {
+    let (name, ascending) = match value.rsplit_once(',') {
+        Some((name, "asc")) => (name, true),
+        Some((name, "desc")) => (name, false),
+        _ => (value, false),
+    };
+    let sort = match name.to_lowercase().as_str() {
+        "id" => CommentSort::Id,
+        "random" => CommentSort::Random,
+        "user" | "author" => CommentSort::User,
+        "post" => CommentSort::Post,
+        "date" | "time" | "creation-date" | "creation-time" => CommentSort::CreationTime,
+        "edit-date" | "edit-time" | "last-edit-date" | "last-edit-time" => CommentSort::LastEditTime,
+        _ => return Err(format!("Unknown sort token {name:?}.")),
+    };
+    Ok((sort, ascending != negated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters(query: &str) -> Vec<(PostFilter, bool)> {
+        parse_post_query(query).unwrap().terms.into_iter().map(|term| (term.filter, term.negated)).collect()
+    }
+
+    #[test]
+    fn tags_and_named_tokens() {
+        assert_eq!(filters("cat -dog* series\\:y id:1..5 tag-count:2,3 safety:questionable"), vec![
+            (PostFilter::Tag("cat".to_string()), false),
+            (PostFilter::Tag("dog*".to_string()), true),
+            (PostFilter::Tag("series:y".to_string()), false),
+            (PostFilter::Id(Criterion::Range(Some(1), Some(5))), false),
+            (PostFilter::TagCount(Criterion::OneOf(vec![2, 3])), false),
+            (PostFilter::Safety(vec!["sketchy".to_string()]), false),
+        ]);
+        assert_eq!(filters("-score:..0"), vec![(PostFilter::Score(Criterion::Range(None, Some(0))), true)]);
+        assert_eq!(filters("special:fav"), vec![(PostFilter::Special(Special::Fav), false)]);
+        assert!(parse_post_query("id:abc").is_err());
+        assert!(parse_post_query("safety:safe..unsafe").is_err());
+        assert!(parse_post_query("sfety:safe").is_err());
+        assert!(parse_post_query("character:x").is_err());
+    }
+
+    #[test]
+    fn dates() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(filters("date:2024-02"), vec![(PostFilter::Date(vec![(Some(day(2024, 2, 1)), Some(day(2024, 3, 1)))]), false)]);
+        assert_eq!(filters("date:2023..2024-01-05"), vec![(PostFilter::Date(vec![(Some(day(2023, 1, 1)), Some(day(2024, 1, 6)))]), false)]);
+        assert!(parse_post_query("date:2024-13").is_err());
+        assert!(parse_post_query("date:262143-12-31").is_err());
+        assert!(parse_post_query("date:262143").is_err());
+        assert!(parse_post_query("date:4294967295").is_err());
+    }
+
+    #[test]
+    fn comments() {
+        let search = parse_comment_query("post:5 -user:carl nice* sort:post,asc").unwrap();
+        assert_eq!(search.terms, vec![
+            CommentTerm { filter: CommentFilter::Post(Criterion::OneOf(vec![5])), negated: false },
+            CommentTerm { filter: CommentFilter::User(vec!["carl".to_string()]), negated: true },
+            CommentTerm { filter: CommentFilter::Text("nice*".to_string()), negated: false },
+        ]);
+        assert_eq!((search.sort, search.ascending), (CommentSort::Post, true));
+        assert!(parse_comment_query("score:1").is_err());
+    }
+
+    #[test]
+    fn sorting() {
+        assert_eq!(parse_post_query("").unwrap().sort, PostSort::Id);
+        let search = parse_post_query("-sort:tag-count").unwrap();
+        assert_eq!((search.sort, search.ascending), (PostSort::TagCount, true));
+        let search = parse_post_query("sort:random").unwrap();
+        assert_eq!((search.sort, search.ascending), (PostSort::Random, false));
+        assert!(parse_post_query("sort:nothing").is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use std::{fs::File, io::{BufWriter, Cursor}};
+use anyhow::Result;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageFormat};
+
+use crate::config::Thumbnails;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Scales image to cover `width`x`height` and crops what doesn't fit.
+/// Animated GIFs are represented by their first frame.
+pub fn crop_thumbnail(content: &[u8], width: u64, height: u64) -> Result<DynamicImage> {
+    let image = image::load_from_memory(content)?;
+    Ok(image.resize_to_fill(width as u32, height as u32, FilterType::Triangle))
+}
+
+fn save_jpeg(image: &DynamicImage, path: &str) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    JpegEncoder::new_with_quality(&mut file, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+    Ok(())
+}
+
+/// Reads post content and writes its thumbnail, sized by `post_width`/`post_height`.
+pub fn generate_post_thumbnail(config: &Thumbnails, content_path: &str, thumbnail_path: &str) -> Result<()> {
+    let content = std::fs::read(content_path)?;
+    let thumbnail = crop_thumbnail(&content, config.post_width, config.post_height)?;
+    save_jpeg(&thumbnail, thumbnail_path)
+}
+
+/// Encodes JPEG post thumbnail from custom image instead of post content.
+pub fn encode_custom_post_thumbnail(config: &Thumbnails, content: &[u8]) -> Result<Vec<u8>> {
+    let thumbnail = crop_thumbnail(content, config.post_width, config.post_height)?;
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY).encode_image(&thumbnail.to_rgb8())?;
+    Ok(encoded)
+}
+
+/// Encodes PNG avatar, sized by `avatar_width`/`avatar_height`.
+pub fn encode_avatar(config: &Thumbnails, content: &[u8]) -> Result<Vec<u8>> {
+    let avatar = crop_thumbnail(content, config.avatar_width, config.avatar_height)?;
+    let mut encoded = Vec::new();
+    avatar.write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)?;
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn crops_to_requested_size() {
+        let mut content = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(640, 200))
+            .write_to(&mut Cursor::new(&mut content), ImageFormat::Png)
+            .unwrap();
+        let thumbnail = crop_thumbnail(&content, 300, 300).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (300, 300));
+    }
+}
@@ -0,0 +1,16 @@
+use std::str::FromStr;
+
+use crate::{config::Thumbnails, db::schemas::user, func::post::get_content_md5, AvatarStyle};
+
+pub fn get_avatar_path(name: &str) -> String {
+    format!("data/avatars/{name}.png")
+}
+
+/// Uploaded avatar for manual style, otherwise Gravatar of email (or name if there is no email).
+pub fn get_avatar_url(config: &Thumbnails, raw_user: &user::Model) -> String {
+    if let Ok(AvatarStyle::Manual) = AvatarStyle::from_str(&raw_user.avatar_style) {
+        return get_avatar_path(&raw_user.name);
+    }
+    let identity = raw_user.email.as_deref().unwrap_or(&raw_user.name).trim().to_lowercase();
+    format!("https://gravatar.com/avatar/{}?d=retro&s={}", get_content_md5(identity.as_bytes()), config.avatar_width)
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::DefaultBodyLimit, middleware::from_extractor, routing::{delete, get, post}, Router
+    extract::DefaultBodyLimit, middleware::from_extractor_with_state, routing::{get, post, put}, Router
 };
 use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
@@ -22,7 +22,7 @@ pub mod error;
 
 // Authentication
 pub mod auth;
-pub use auth::RequireAuth;
+pub use auth::{CurrentUser, RequireAuth};
 
 // Database
 pub mod db;
@@ -76,6 +76,7 @@ async fn main() {
     });
 
     let listen = state.config.listen.clone();
+    let upload_limit = state.config.limits.largest_upload_size();
     
     debug!("State ready!");
     trace!("Data:\n{:?}", state);
@@ -86,17 +87,37 @@ async fn main() {
         .route("/test2", get(api::test::newtest2))
         // TODO: Удалить мусор выше
         .route("/posts/", get(api::post::list_of_posts))
+        .route("/posts", post(api::post::create_post))
         .route("/posts/reverse-search", post(api::post::reverse_post_search))
-        .route("/post/:id", get(api::post::get_post_by_id))
-        .route("/user/:user", get(api::user::get_user))
+        .route("/post/:id", get(api::post::get_post_by_id).put(api::post::update_post).layer(DefaultBodyLimit::max(upload_limit)))
+        .route("/post/:id/score", put(api::post::rate_post))
+        .route("/post/:id/favorite", post(api::post::favorite_post).delete(api::post::unfavorite_post))
+        .route("/comments/", get(api::comment::list_comments))
+        .route("/comments", post(api::comment::create_comment))
+        .route("/comment/:id", get(api::comment::get_comment).put(api::comment::update_comment).delete(api::comment::delete_comment))
+        .route("/comment/:id/score", put(api::comment::rate_comment))
+        .route("/user/:user", get(api::user::get_user).put(api::user::update_user).delete(api::user::delete_user).layer(DefaultBodyLimit::max(upload_limit)))
         .route("/user-tokens/:user", get(api::usertoken::list_usertokens))
         .route("/user-token/:user", post(api::usertoken::create_usertoken))
-        .route("/user-token/:user/:token", delete(api::usertoken::delete_usertoken))
-        .route("/users", post(api::user::create_user))
-        .route("/uploads", post(api::data::upload).layer(DefaultBodyLimit::max(1073741824))) // 1 GB
-        // TODO: Брать значение на максимально возможный для загрузки файл из конфига
-        .route_layer(from_extractor::<RequireAuth>()) // Auth, functions lower doesn't require it.
+        .route("/user-token/:user/:token", put(api::usertoken::update_usertoken).delete(api::usertoken::delete_usertoken))
+        .route("/users", get(api::user::list_users).post(api::user::create_user).layer(DefaultBodyLimit::max(upload_limit)))
+        .route("/tags", get(api::tag::list_tags).post(api::tag::create_tag))
+        .route("/tag/:name", get(api::tag::get_tag).put(api::tag::update_tag).delete(api::tag::delete_tag))
+        .route("/tag-merge", post(api::tag::merge_tags))
+        .route("/tag-categories", get(api::tag_category::list_tag_categories).post(api::tag_category::create_tag_category))
+        .route("/tag-category/:name", get(api::tag_category::get_tag_category).put(api::tag_category::update_tag_category).delete(api::tag_category::delete_tag_category))
+        .route("/tag-category/:name/default", put(api::tag_category::set_default_tag_category))
+        .route("/pools/", get(api::pool::list_pools))
+        .route("/pool", post(api::pool::create_pool))
+        .route("/pool/:id", get(api::pool::get_pool).put(api::pool::update_pool).delete(api::pool::delete_pool))
+        .route("/pool-merge", post(api::pool::merge_pools))
+        .route("/pool-categories", get(api::pool_category::list_pool_categories).post(api::pool_category::create_pool_category))
+        .route("/pool-category/:name", get(api::pool_category::get_pool_category).put(api::pool_category::update_pool_category).delete(api::pool_category::delete_pool_category))
+        .route("/pool-category/:name/default", put(api::pool_category::set_default_pool_category))
+        .route("/uploads", post(api::data::upload).layer(DefaultBodyLimit::max(upload_limit)))
+        .route_layer(from_extractor_with_state::<RequireAuth, _>(state.clone())) // Auth, functions lower doesn't require it.
         .route("/info", get(api::info::server_info))
+        .route("/generated-thumbnails/:name", get(api::post::get_post_thumbnail)) // Missing thumbnails are generated on demand
         .fallback_service(api::data::data_static())
         .with_state(state)
         .layer(TraceLayer::new_for_http());
@@ -142,7 +163,7 @@ async fn shutdown_signal() {
     info!("Terminate signal received");
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UserRank {
     #[serde(rename = "administrator")]
     Administrator,
@@ -160,6 +181,33 @@ pub enum UserRank {
     Nobody,
 }
 
+impl UserRank {
+    /// Position in rank hierarchy, from nobody (lowest) to administrator.
+    fn level(&self) -> u8 {
+        match self {
+            UserRank::Nobody => 0,
+            UserRank::Anonymous => 1,
+            UserRank::Restricted => 2,
+            UserRank::Regular => 3,
+            UserRank::Power => 4,
+            UserRank::Moderator => 5,
+            UserRank::Administrator => 6,
+        }
+    }
+}
+
+impl PartialOrd for UserRank {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UserRank {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.level().cmp(&other.level())
+    }
+}
+
 impl FromStr for UserRank {
     fn from_str(str: &str) -> std::result::Result<Self, Self::Err> {
         match str {
@@ -177,17 +225,17 @@ impl FromStr for UserRank {
     type Err = ();
 }
 
-impl ToString for UserRank {
-    fn to_string(&self) -> String {
-        match self {
-            UserRank::Administrator => String::from("administrator"),
-            UserRank::Moderator => String::from("moderator"),
-            UserRank::Power => String::from("power"),
-            UserRank::Regular => String::from("regular"),
-            UserRank::Restricted => String::from("restricted"),
-            UserRank::Anonymous => String::from("anonymous"),
-            UserRank::Nobody => String::from("nobody"),
-        }
+impl std::fmt::Display for UserRank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UserRank::Administrator => "administrator",
+            UserRank::Moderator => "moderator",
+            UserRank::Power => "power",
+            UserRank::Regular => "regular",
+            UserRank::Restricted => "restricted",
+            UserRank::Anonymous => "anonymous",
+            UserRank::Nobody => "nobody",
+        })
     }
 }
 
@@ -211,96 +259,11 @@ impl FromStr for AvatarStyle {
     type Err = ();
 }
 
-impl ToString for AvatarStyle {
-    fn to_string(&self) -> String {
-        match self {
-            AvatarStyle::Gravatar => String::from("gravatar"),
-            AvatarStyle::Manual => String::from("manual"),
-        }
+impl std::fmt::Display for AvatarStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AvatarStyle::Gravatar => "gravatar",
+            AvatarStyle::Manual => "manual",
+        })
     }
 }
-
-// #[allow(dead_code)]
-// #[derive(Serialize, Debug, thiserror::Error)]
-// enum ApiErrorTypes {
-//     MissingRequiredFileError,
-//     MissingRequiredParameterError,
-//     InvalidParameterError,
-//     IntegrityError,
-//     SearchError,
-//     AuthError,
-//     PostNotFoundError,
-//     PostAlreadyFeaturedError,
-//     PostAlreadyUploadedError,
-//     InvalidPostIdError,
-//     InvalidPostSafetyError,
-//     InvalidPostSourceError,
-//     InvalidPostContentError,
-//     InvalidPostRelationError,
-//     InvalidPostNoteError,
-//     InvalidPostFlagError,
-//     InvalidFavoriteTargetError,
-//     InvalidCommentIdError,
-//     CommentNotFoundError,
-//     EmptyCommentTextError,
-//     InvalidScoreTargetError,
-//     InvalidScoreValueError,
-//     TagCategoryNotFoundError,
-//     TagCategoryAlreadyExistsError,
-//     TagCategoryIsInUseError,
-//     InvalidTagCategoryNameError,
-//     InvalidTagCategoryColorError,
-//     TagNotFoundError,
-//     TagAlreadyExistsError,
-//     TagIsInUseError,
-//     InvalidTagNameError,
-//     InvalidTagRelationError,
-//     InvalidTagCategoryError,
-//     InvalidTagDescriptionError,
-//     UserNotFoundError,
-//     UserAlreadyExistsError,
-//     InvalidUserNameError,
-//     InvalidEmailError,
-//     InvalidPasswordError,
-//     InvalidRankError,
-//     InvalidAvatarError,
-//     ProcessingError,
-//     ValidationError,
-// }
-//
-// #[derive(Serialize)]
-// struct ApiErrorResponse {
-//     name: String,
-//     title: String,
-//     description: String,
-// }
-//
-// // Make our own error that wraps `anyhow::Error`.
-// struct AppError(anyhow::Error);
-//
-// // Tell axum how to convert `AppError` into a response.
-// impl IntoResponse for AppError {
-//     fn into_response(self) -> Response {
-//         (
-//             StatusCode::INTERNAL_SERVER_ERROR,
-//             [("Content-Type", "application/json")],
-//             serde_json::to_string_pretty(&ApiErrorResponse {
-//                 name: "ValidationError".to_string(),
-//                 title: self.0.to_string(),
-//                 description: "Error".to_string(),
-//             })
-//             .unwrap(),
-//         ).into_response()
-//     }
-// }
-//
-// // This enables using `?` on functions that return `Result<_, anyhow::Error>` to turn them into
-// // `Result<_, AppError>`. That way you don't need to do that manually.
-// impl<E> From<E> for AppError
-// where
-//     E: Into<anyhow::Error>,
-// {
-//     fn from(err: E) -> Self {
-//         Self(err.into())
-//     }
-// }